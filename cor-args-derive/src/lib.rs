@@ -0,0 +1,258 @@
+//! Derive macro for the [`cor-args`](https://crates.io/crates/cor-args) crate.
+//!
+//! This crate is not meant to be used directly. Enable the `derive` feature of `cor-args` and
+//! use `cor_args::CorArgs` instead.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Expr, ExprLit, Field, Fields, GenericArgument, Lit,
+    LitChar, LitStr, Meta, PathArguments, Token, Type,
+};
+
+/// Derives `cor_args::CorArgs` for a struct with named fields.
+///
+/// See the documentation of the `cor_args::CorArgs` trait for the supported `#[cor(...)]`
+/// attributes.
+#[proc_macro_derive(CorArgs, attributes(cor))]
+pub fn derive_cor_args(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// The sources of a single field, as configured by its `#[cor(...)]` attribute.
+struct FieldSpec {
+    /// The field name, which is also the key the field is resolved by.
+    key: String,
+    long: Option<String>,
+    short: Option<char>,
+    env: Option<String>,
+    file_key: String,
+    default: Option<String>,
+    /// The field's doc comment, used as the argument's help text.
+    help: Option<String>,
+}
+
+impl FieldSpec {
+    fn parse(field: &Field) -> syn::Result<Self> {
+        let ident = field.ident.as_ref().expect("named field");
+        let key = ident.to_string().trim_start_matches("r#").to_string();
+        let mut spec = FieldSpec {
+            long: None,
+            short: None,
+            env: None,
+            file_key: key.clone(),
+            default: None,
+            help: doc_comment(field),
+            key,
+        };
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("cor"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("long") {
+                    spec.long = Some(if meta.input.peek(Token![=]) {
+                        meta.value()?.parse::<LitStr>()?.value()
+                    } else {
+                        spec.key.replace('_', "-")
+                    });
+                } else if meta.path.is_ident("short") {
+                    spec.short = Some(meta.value()?.parse::<LitChar>()?.value());
+                } else if meta.path.is_ident("env") {
+                    spec.env = Some(if meta.input.peek(Token![=]) {
+                        meta.value()?.parse::<LitStr>()?.value()
+                    } else {
+                        spec.key.to_uppercase()
+                    });
+                } else if meta.path.is_ident("file_key") {
+                    spec.file_key = meta.value()?.parse::<LitStr>()?.value();
+                } else if meta.path.is_ident("default") {
+                    spec.default = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else {
+                    return Err(meta.error("unsupported cor attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(spec)
+    }
+
+    fn has_arg(&self) -> bool {
+        self.long.is_some() || self.short.is_some()
+    }
+}
+
+/// Joins the lines of a field's doc comment, if it has one.
+fn doc_comment(field: &Field) -> Option<String> {
+    let lines: Vec<String> = field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(doc), ..
+                }) => Some(doc.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join(" ").trim().to_string())
+    }
+}
+
+/// Returns `T` if the type is `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first() {
+        Some(GenericArgument::Type(inner)) if args.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
+
+/// Returns whether the type is `bool`.
+fn is_bool(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("bool"))
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "CorArgs can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "CorArgs can only be derived for structs",
+            ))
+        }
+    };
+    let specs = fields
+        .iter()
+        .map(FieldSpec::parse)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let args = fields
+        .iter()
+        .zip(&specs)
+        .filter(|(_, spec)| spec.has_arg())
+        .map(|(field, spec)| {
+            let key = &spec.key;
+            let long = spec.long.as_ref().map(|long| quote!(.long(#long)));
+            let short = spec.short.map(|short| quote!(.short(#short)));
+            let help = spec.help.as_ref().map(|help| quote!(.help(#help)));
+            let ty = option_inner(&field.ty).unwrap_or(&field.ty);
+            let action = is_bool(ty)
+                .then(|| quote!(.action(::cor_args::__private::clap::ArgAction::SetTrue)));
+            quote!(::cor_args::__private::clap::Arg::new(#key) #long #short #help #action)
+        });
+
+    let arg_keys = specs.iter().filter(|spec| spec.has_arg()).map(|spec| {
+        let key = &spec.key;
+        quote!(.map(#key, #key))
+    });
+    let env_keys = specs.iter().filter_map(|spec| {
+        let key = &spec.key;
+        spec.env.as_ref().map(|env| quote!(.map(#key, #env)))
+    });
+    let file_keys = specs.iter().map(|spec| {
+        let key = &spec.key;
+        let file_key = &spec.file_key;
+        quote!(.map(#key, #file_key))
+    });
+    let defaults = specs.iter().filter_map(|spec| {
+        let key = &spec.key;
+        spec.default.as_ref().map(|default| {
+            quote! {
+                chain.push(
+                    ::cor_args::__private::KeyMapHandler::new(::cor_args::DefaultHandler::new(#default))
+                        .map(#key, #key),
+                );
+            }
+        })
+    });
+
+    let values = fields.iter().zip(&specs).map(|(field, spec)| {
+        let ident = &field.ident;
+        let key = &spec.key;
+        match option_inner(&field.ty) {
+            Some(inner) => quote!(#ident: ::cor_args::Handler::get::<#inner>(&handler, #key)?),
+            None if is_bool(&field.ty) => {
+                quote!(#ident: ::cor_args::Handler::get::<bool>(&handler, #key)?.unwrap_or(false))
+            }
+            None => {
+                let ty = &field.ty;
+                quote! {
+                    #ident: ::cor_args::Handler::get::<#ty>(&handler, #key)?.ok_or_else(|| {
+                        ::cor_args::Error::MissingKey { key: #key.to_string() }
+                    })?
+                }
+            }
+        }
+    });
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::cor_args::CorArgs for #name #ty_generics #where_clause {
+            fn args() -> ::std::vec::Vec<::cor_args::__private::clap::Arg> {
+                ::std::vec![#(#args),*]
+            }
+
+            fn chain<'a>(
+                matches: &'a ::cor_args::__private::clap::ArgMatches,
+                config_file: ::std::option::Option<::std::path::PathBuf>,
+            ) -> ::cor_args::Chain<'a> {
+                let mut chain = ::cor_args::Chain::new();
+                chain.push(
+                    ::cor_args::__private::KeyMapHandler::new(::cor_args::ArgHandler::new(matches))
+                        #(#arg_keys)*,
+                );
+                chain.push(
+                    ::cor_args::__private::KeyMapHandler::new(::cor_args::EnvHandler::new())
+                        #(#env_keys)*,
+                );
+                if let ::std::option::Option::Some(config_file) = config_file {
+                    chain.push(
+                        ::cor_args::__private::KeyMapHandler::new(::cor_args::JSONFileHandler::new(config_file))
+                            #(#file_keys)*,
+                    );
+                }
+                #(#defaults)*
+                chain
+            }
+
+            fn from_handler<H: ::cor_args::Handler + ?Sized>(
+                handler: &H,
+            ) -> ::std::result::Result<Self, ::cor_args::Error> {
+                ::std::result::Result::Ok(Self {
+                    #(#values),*
+                })
+            }
+        }
+    })
+}
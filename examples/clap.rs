@@ -1,5 +1,5 @@
 #[cfg(feature = "clap")]
-use cor_args::{ArgHandler, DefaultHandler, EnvHandler, FileHandler, Handler};
+use cor_args::{ArgHandler, Chain, DefaultHandler, EnvHandler, FileHandler, Handler};
 
 /// This example can be run multiple ways to test out the Chain of Responsibility.
 ///
@@ -29,8 +29,10 @@ fn main() {
         .arg(clap::Arg::new("verbosity").long("verbosity"))
         .get_matches();
 
-    let handler = ArgHandler::new(&args).next(Box::new(
-        EnvHandler::new().next(Box::new(
+    let handler = Chain::new()
+        .with(ArgHandler::new(&args))
+        .with(EnvHandler::new())
+        .with(
             FileHandler::new(
                 std::env::current_dir()
                     .unwrap()
@@ -39,10 +41,11 @@ fn main() {
                     .to_str()
                     .unwrap(),
             )
-            .next(Box::new(DefaultHandler::new("trace"))),
-        )),
-    ));
+            .trim(true),
+        )
+        .with(DefaultHandler::new("trace"));
     // Safe to unwrap since we end the chain with a DefaultHandler which will always return "trace".
+    // Sources which fail, e.g. a malformed file, are skipped by `handle_request`.
     let verbosity = handler.handle_request("verbosity").unwrap();
     println!("verbosity = {}", verbosity);
 }
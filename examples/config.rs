@@ -1,5 +1,5 @@
 #[cfg(feature = "config")]
-use cor_args::{ConfigHandler, DefaultHandler, EnvHandler, FileHandler, Handler};
+use cor_args::{Chain, ConfigHandler, DefaultHandler, EnvHandler, FileHandler, Handler};
 
 /// This example can be run multiple ways to test out the Chain of Responsibility.
 ///
@@ -45,8 +45,10 @@ fn main() {
         .build()
         .unwrap();
 
-    let handler = ConfigHandler::new(Box::new(config)).next(Box::new(
-        EnvHandler::new().next(Box::new(
+    let handler = Chain::new()
+        .with(ConfigHandler::new(Box::new(config)))
+        .with(EnvHandler::new())
+        .with(
             FileHandler::new(
                 std::env::current_dir()
                     .unwrap()
@@ -55,10 +57,11 @@ fn main() {
                     .to_str()
                     .unwrap(),
             )
-            .next(Box::new(DefaultHandler::new("trace"))),
-        )),
-    ));
+            .trim(true),
+        )
+        .with(DefaultHandler::new("trace"));
     // Safe to unwrap since we end the chain with a DefaultHandler which will always return "trace".
+    // Sources which fail, e.g. a malformed file, are skipped by `handle_request`.
     let verbosity = handler.handle_request("verbosity").unwrap();
     println!("verbosity = {}", verbosity);
 }
@@ -0,0 +1,55 @@
+#[cfg(feature = "derive")]
+use cor_args::CorArgs;
+
+/// The settings of the example, each of which can be set by several sources.
+#[cfg(feature = "derive")]
+#[derive(CorArgs)]
+struct Settings {
+    /// The verbosity of the output.
+    #[cor(long, env, default = "trace")]
+    verbosity: String,
+    /// The port to listen on.
+    #[cor(long, short = 'p', env = "EXAMPLE_PORT", default = "8080")]
+    port: u16,
+}
+
+/// This example can be run multiple ways to test out the Chain of Responsibility.
+///
+/// # Testing the ArgHandler
+/// ```bash
+/// cargo run --features derive --example derive -- --verbosity debug -p 9090
+/// ```
+///
+/// # Testing the EnvHandler
+/// ```bash
+/// VERBOSITY=info EXAMPLE_PORT=9090 cargo run --features derive --example derive
+/// ```
+///
+/// # Testing the JSONFileHandler
+/// ```bash
+/// echo '{"verbosity": "debug", "port": 9090}' > settings.json
+/// cargo run --features derive --example derive
+/// ```
+///
+/// # Testing the DefaultHandler
+/// ```bash
+/// cargo run --features derive --example derive
+/// ```
+#[cfg(feature = "derive")]
+fn main() {
+    let args = clap::Command::new("test_app")
+        .args(Settings::args())
+        .get_matches();
+
+    let config_file = std::env::current_dir().unwrap().join("settings.json");
+    let settings = Settings::from_matches(&args, Some(config_file)).unwrap();
+    println!("verbosity = {}", settings.verbosity);
+    println!("port = {}", settings.port);
+}
+
+#[cfg(not(feature = "derive"))]
+fn main() {
+    eprintln!("You need to run with the 'derive' feature enabled!");
+    eprintln!("Try:");
+    eprintln!("\tcargo run --features derive --example derive");
+}
@@ -0,0 +1,36 @@
+use cor_args::{Chain, ConfigDiscovery, DefaultHandler, EnvHandler, Handler};
+
+/// This example can be run multiple ways to test out configuration file discovery.
+///
+/// # Testing the EnvHandler
+/// ```bash
+/// verbosity=info cargo run --example discovery
+/// ```
+///
+/// # Testing a `.<app>rc` file in the current directory or one of its ancestors
+/// ```bash
+/// echo '{"verbosity": "debug"}' > .discoveryrc
+/// cargo run --example discovery
+/// ```
+///
+/// # Testing the user's configuration directory
+/// ```bash
+/// mkdir -p ~/.config/discovery
+/// echo 'verbosity = warn' > ~/.config/discovery/config.ini
+/// cargo run --example discovery
+/// ```
+///
+/// # Testing the DefaultHandler
+/// ```bash
+/// cargo run --example discovery
+/// ```
+fn main() {
+    let handler = Chain::new()
+        .with(EnvHandler::new())
+        .with(ConfigDiscovery::new("discovery").chain())
+        .with(DefaultHandler::new("trace"));
+    // Safe to unwrap since we end the chain with a DefaultHandler which will always return "trace".
+    // Sources which fail, e.g. a malformed file, are skipped by `handle_request`.
+    let verbosity = handler.handle_request("verbosity").unwrap();
+    println!("verbosity = {}", verbosity);
+}
@@ -1,4 +1,4 @@
-use cor_args::{DefaultHandler, EnvHandler, FileHandler, Handler};
+use cor_args::{Chain, DefaultHandler, EnvHandler, FileHandler, Handler};
 
 /// This example can be run multiple ways to test out the Chain of Responsibility.
 ///
@@ -18,18 +18,22 @@ use cor_args::{DefaultHandler, EnvHandler, FileHandler, Handler};
 /// cargo run --example simple
 /// ```
 fn main() {
-    let handler = EnvHandler::new().next(Box::new(
-        FileHandler::new(
-            std::env::current_dir()
-                .unwrap()
-                .join("verbosity.txt")
-                .as_path()
-                .to_str()
-                .unwrap(),
+    let handler = Chain::new()
+        .with(EnvHandler::new())
+        .with(
+            FileHandler::new(
+                std::env::current_dir()
+                    .unwrap()
+                    .join("verbosity.txt")
+                    .as_path()
+                    .to_str()
+                    .unwrap(),
+            )
+            .trim(true),
         )
-        .next(Box::new(DefaultHandler::new("trace"))),
-    ));
+        .with(DefaultHandler::new("trace"));
     // Safe to unwrap since we end the chain with a DefaultHandler which will always return "trace".
+    // Sources which fail, e.g. a malformed file, are skipped by `handle_request`.
     let verbosity = handler.handle_request("verbosity").unwrap();
     println!("verbosity = {}", verbosity);
 }
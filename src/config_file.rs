@@ -0,0 +1,746 @@
+//! A handler for configuration files of any supported format.
+//!
+//! See [`ConfigFileHandler`] for details.
+
+use std::path::Path;
+use std::sync::RwLock;
+
+use super::*;
+
+/// The format of a configuration file.
+///
+/// Support for some formats depends on a feature of this crate:
+///
+/// | Format                     | Feature |
+/// |----------------------------|---------|
+/// | [`FileFormat::Json5`]      | `json5` |
+/// | [`FileFormat::Toml`]       | `toml`  |
+/// | [`FileFormat::Yaml`]       | `yaml`  |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FileFormat {
+    /// JSON, read by a [`JSONFileHandler`].
+    Json,
+    /// JSON5 or JSONC, read by a `Json5FileHandler`.
+    Json5,
+    /// TOML, read by a `TomlFileHandler`.
+    Toml,
+    /// YAML, read by a `YamlFileHandler`.
+    Yaml,
+    /// INI, read by an [`IniFileHandler`].
+    Ini,
+    /// dotenv, read by a [`DotenvHandler`].
+    Dotenv,
+    /// Java properties, read by a [`PropertiesFileHandler`].
+    Properties,
+}
+
+impl FileFormat {
+    /// Determines the format of a file from its name.
+    ///
+    /// The extensions `json`, `json5`, `jsonc`, `toml`, `yaml`, `yml`, `ini`, `cfg`, `env` and
+    /// `properties` are recognized, ignoring case. Files named `.env`, or starting with `.env.`
+    /// such as `.env.local`, are recognized as dotenv files.
+    ///
+    /// # Returns
+    ///
+    /// The format, or `None` if the name does not indicate one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::FileFormat;
+    ///
+    /// assert_eq!(FileFormat::from_path("app.yml"), Some(FileFormat::Yaml));
+    /// assert_eq!(FileFormat::from_path(".env.local"), Some(FileFormat::Dotenv));
+    /// assert_eq!(FileFormat::from_path("app.conf"), None);
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let path = path.as_ref();
+        let file_name = path.file_name()?.to_string_lossy();
+        if file_name == ".env" || file_name.starts_with(".env.") {
+            return Some(FileFormat::Dotenv);
+        }
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        match extension.as_str() {
+            "json" => Some(FileFormat::Json),
+            "json5" | "jsonc" => Some(FileFormat::Json5),
+            "toml" => Some(FileFormat::Toml),
+            "yaml" | "yml" => Some(FileFormat::Yaml),
+            "ini" | "cfg" => Some(FileFormat::Ini),
+            "env" => Some(FileFormat::Dotenv),
+            "properties" => Some(FileFormat::Properties),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format of a file from its content.
+    ///
+    /// Content that parses as JSON is taken to be JSON. Otherwise, the lines of the file are
+    /// checked first, as this is much cheaper than parsing the content as TOML or YAML:
+    ///
+    /// * Content with a `[section]` header and a value TOML does not accept, such as an unquoted
+    ///   word or a value after `:`, is taken to be INI.
+    /// * Content whose keys are all environment variable names is taken to be dotenv.
+    /// * Content of unindented `key=value` or `key: value` lines, without the quoting, lists or
+    ///   nesting of YAML, is taken to be Java properties. If all of its lines are `key: value`
+    ///   lines which parse as a YAML mapping, it is taken to be YAML instead, as far as YAML is
+    ///   supported.
+    ///
+    /// Any other content that parses as TOML or a YAML mapping is taken to be of that format, as
+    /// far as the format is supported. Failing that, content with a `[section]` header is taken
+    /// to be INI and anything else is taken to be Java properties.
+    fn sniff(content: &str) -> Self {
+        if serde_json::from_str::<serde::de::IgnoredAny>(content).is_ok() {
+            return FileFormat::Json;
+        }
+        let lines: Vec<&str> = content
+            .lines()
+            .filter(|line| {
+                let line = line.trim();
+                !line.is_empty() && !line.starts_with('#') && !line.starts_with(';')
+            })
+            .collect();
+        let is_header = |line: &&str| line.trim_start().starts_with('[');
+        /// Splits a key/value line into its separator, `=` or `:`, and its value.
+        fn split(line: &str) -> Option<(char, &str)> {
+            let i = line.find(['=', ':'])?;
+            Some((line[i..].chars().next()?, line[i + 1..].trim()))
+        }
+        let is_bare = |value: &str| {
+            !value.starts_with(|c: char| c.is_ascii_digit() || "+-\"'[{".contains(c))
+                && !["true", "false", "inf", "nan"].contains(&value)
+        };
+        if lines.iter().any(is_header)
+            && !lines.iter().any(|line| line.trim_start().starts_with("[["))
+            && lines.iter().filter(|line| !is_header(line)).any(|line| {
+                split(line).is_some_and(|(separator, value)| separator == ':' || is_bare(value))
+            })
+        {
+            return FileFormat::Ini;
+        }
+        let is_variable = |line: &&str| {
+            let line = line.trim_start();
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            line.split_once('=').is_some_and(|(key, _)| {
+                let key = key.trim();
+                !key.is_empty()
+                    && !key.starts_with(|c: char| c.is_ascii_digit())
+                    && key
+                        .chars()
+                        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+            })
+        };
+        if !lines.is_empty() && lines.iter().all(is_variable) {
+            return FileFormat::Dotenv;
+        }
+        let is_property = |line: &&str| {
+            !line.starts_with(|c: char| c.is_whitespace() || c == '[' || c == '-')
+                && split(line).is_some_and(|(_, value)| {
+                    !value.starts_with(['[', '{', '"', '\'', '&', '*', '!', '|', '>'])
+                })
+        };
+        #[cfg(feature = "yaml")]
+        let is_yaml = || {
+            serde_yaml::from_str::<serde_yaml::Value>(content).is_ok_and(|value| value.is_mapping())
+        };
+        if !lines.is_empty() && lines.iter().all(is_property) {
+            #[cfg(feature = "yaml")]
+            if lines
+                .iter()
+                .all(|line| split(line).is_some_and(|(separator, _)| separator == ':'))
+                && is_yaml()
+            {
+                return FileFormat::Yaml;
+            }
+            return FileFormat::Properties;
+        }
+        #[cfg(feature = "toml")]
+        if content.parse::<toml::Table>().is_ok() {
+            return FileFormat::Toml;
+        }
+        #[cfg(feature = "yaml")]
+        if is_yaml() {
+            return FileFormat::Yaml;
+        }
+        if lines.iter().any(is_header) {
+            return FileFormat::Ini;
+        }
+        FileFormat::Properties
+    }
+}
+
+/// A handler for retrieving values from a configuration file of any supported format.
+///
+/// The format is taken from the name of the file, as described for [`FileFormat::from_path`].
+/// If the name does not indicate a format, it is guessed from the content of the file. Once the
+/// format is known, requests are answered by the handler for that format, e.g. a
+/// [`JSONFileHandler`] for JSON files, so keys are resolved as described for that handler.
+///
+/// Instead of a single file, a base name and candidate extensions can be given with
+/// [`ConfigFileHandler::candidates`], so users can write their configuration in whichever format
+/// they prefer.
+///
+/// The file and its format are determined on the first request for which a candidate file
+/// exists, and are reused by later requests. With [`ConfigFileHandler::revalidate`], the
+/// candidates are checked again on each request.
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{ConfigFileHandler, Handler};
+///
+/// // Create a new ConfigFileHandler specifying a path to a file of any supported format.
+/// let handler = ConfigFileHandler::new("app.yaml");
+///
+/// // Handle a configuration request matching a `"some_key"` within `app.yaml`
+/// let value = handler.handle_request("some_key");
+/// ```
+pub struct ConfigFileHandler {
+    /// The paths of the candidate files, in order of preference.
+    candidates: Vec<PathBuf>,
+    /// The format of the file, if not determined from its name or content.
+    format: Option<FileFormat>,
+    /// Whether bare keys which occur more than once are reported as ambiguous.
+    strict: bool,
+    /// Whether the file is parsed again when it changes.
+    revalidate: bool,
+    /// How a chain treats a failure to read or parse the file.
+    error_policy: ErrorPolicy,
+    /// The path of the file and the handler for it, once they have been determined.
+    handler: RwLock<Option<(PathBuf, Arc<dyn Handler + Send + Sync>)>>,
+}
+
+impl ConfigFileHandler {
+    /// Creates a new `ConfigFileHandler` with the specified file path.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - The path to the configuration file from which values are to be retrieved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::ConfigFileHandler;
+    ///
+    /// let handler = ConfigFileHandler::new("app.toml");
+    /// ```
+    #[allow(dead_code)]
+    pub fn new<P>(file_path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self::from_candidates(vec![file_path.into()])
+    }
+
+    /// Creates a new `ConfigFileHandler` for the first existing file with the specified base
+    /// name and one of the specified extensions.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_path` - The path to the configuration file, without an extension.
+    /// * `extensions` - The candidate extensions, without a leading `.`, in order of preference.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::ConfigFileHandler;
+    ///
+    /// // Read `app.toml`, or else `app.yaml`, or else `app.json`.
+    /// let handler = ConfigFileHandler::candidates("app", &["toml", "yaml", "json"]);
+    /// ```
+    #[allow(dead_code)]
+    pub fn candidates<P>(base_path: P, extensions: &[&str]) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        let base_path = base_path.into();
+        let candidates = extensions
+            .iter()
+            .map(|extension| {
+                let mut file_name = base_path.clone().into_os_string();
+                file_name.push(".");
+                file_name.push(extension);
+                PathBuf::from(file_name)
+            })
+            .collect();
+        Self::from_candidates(candidates)
+    }
+
+    fn from_candidates(candidates: Vec<PathBuf>) -> Self {
+        ConfigFileHandler {
+            candidates,
+            format: None,
+            strict: false,
+            revalidate: false,
+            error_policy: ErrorPolicy::default(),
+            handler: RwLock::new(None),
+        }
+    }
+
+    /// Sets the format of the file, instead of determining it from its name or content.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - The format of the file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::{ConfigFileHandler, FileFormat};
+    ///
+    /// let handler = ConfigFileHandler::new("app.conf").format(FileFormat::Ini);
+    /// ```
+    #[allow(dead_code)]
+    pub fn format(mut self, format: FileFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Sets whether bare keys which occur more than once are reported as ambiguous.
+    ///
+    /// Only applies to formats read by a handler with a `strict` mode, such as
+    /// [`JSONFileHandler::strict`].
+    ///
+    /// # Arguments
+    ///
+    /// * `strict` - `true` to report ambiguous keys, `false` to return the first match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::ConfigFileHandler;
+    ///
+    /// let handler = ConfigFileHandler::new("app.json").strict(true);
+    /// ```
+    #[allow(dead_code)]
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets whether the file is parsed again when it changes.
+    ///
+    /// See [`JSONFileHandler::revalidate`]. The candidates are checked again on each request as
+    /// well, so a preferred candidate which is created later, or the removal of the file in use,
+    /// is picked up. The format of a file is only determined again when another file is chosen.
+    ///
+    /// # Arguments
+    ///
+    /// * `revalidate` - `true` to pick up changes to the file, `false` to parse it only once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::ConfigFileHandler;
+    ///
+    /// let handler = ConfigFileHandler::new("app.json").revalidate(true);
+    /// ```
+    #[allow(dead_code)]
+    pub fn revalidate(mut self, revalidate: bool) -> Self {
+        self.revalidate = revalidate;
+        self
+    }
+
+    /// Sets how a chain treats a failure to read or parse the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `error_policy` - The policy applied to read and parse errors. Defaults to
+    ///   [`ErrorPolicy::Fail`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::{ConfigFileHandler, ErrorPolicy};
+    ///
+    /// let handler = ConfigFileHandler::new("app.json").on_error(ErrorPolicy::Warn);
+    /// ```
+    #[allow(dead_code)]
+    pub fn on_error(mut self, error_policy: ErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// Returns the handler for the file, determining the file and its format if necessary.
+    ///
+    /// # Returns
+    ///
+    /// The handler, `None` if no candidate file exists, an [`Error::Io`] if the file could not
+    /// be read to guess its format, or an [`Error::Parse`] if its format is not supported.
+    fn handler(&self) -> Result<Option<Arc<dyn Handler + Send + Sync>>, Error> {
+        let cached = self.handler.read().unwrap().clone();
+        if !self.revalidate {
+            if let Some((_, handler)) = &cached {
+                return Ok(Some(Arc::clone(handler)));
+            }
+        }
+        let Some(path) = self.candidates.iter().find(|path| path.is_file()) else {
+            return Ok(None);
+        };
+        if let Some((cached_path, handler)) = cached {
+            if cached_path == *path {
+                return Ok(Some(handler));
+            }
+        }
+        let format = match self.format.or_else(|| FileFormat::from_path(path)) {
+            Some(format) => format,
+            None => match FileHandler::new(path).read()? {
+                Some(content) => FileFormat::sniff(&content),
+                None => return Ok(None),
+            },
+        };
+        let handler = self.build(path, format)?;
+        *self.handler.write().unwrap() = Some((path.clone(), Arc::clone(&handler)));
+        Ok(Some(handler))
+    }
+
+    /// Creates the handler for a file of the specified format.
+    fn build(
+        &self,
+        path: &Path,
+        format: FileFormat,
+    ) -> Result<Arc<dyn Handler + Send + Sync>, Error> {
+        let handler: Arc<dyn Handler + Send + Sync> = match format {
+            FileFormat::Json => Arc::new(
+                JSONFileHandler::new(path)
+                    .strict(self.strict)
+                    .revalidate(self.revalidate),
+            ),
+            #[cfg(feature = "json5")]
+            FileFormat::Json5 => Arc::new(
+                Json5FileHandler::new(path)
+                    .strict(self.strict)
+                    .revalidate(self.revalidate),
+            ),
+            #[cfg(not(feature = "json5"))]
+            FileFormat::Json5 => return Err(unsupported(path, format, "json5")),
+            #[cfg(feature = "toml")]
+            FileFormat::Toml => Arc::new(
+                TomlFileHandler::new(path)
+                    .strict(self.strict)
+                    .revalidate(self.revalidate),
+            ),
+            #[cfg(not(feature = "toml"))]
+            FileFormat::Toml => return Err(unsupported(path, format, "toml")),
+            #[cfg(feature = "yaml")]
+            FileFormat::Yaml => Arc::new(
+                YamlFileHandler::new(path)
+                    .strict(self.strict)
+                    .revalidate(self.revalidate),
+            ),
+            #[cfg(not(feature = "yaml"))]
+            FileFormat::Yaml => return Err(unsupported(path, format, "yaml")),
+            FileFormat::Ini => Arc::new(IniFileHandler::new(path).revalidate(self.revalidate)),
+            FileFormat::Dotenv => Arc::new(DotenvHandler::new(path).revalidate(self.revalidate)),
+            FileFormat::Properties => {
+                Arc::new(PropertiesFileHandler::new(path).revalidate(self.revalidate))
+            }
+        };
+        Ok(handler)
+    }
+}
+
+/// Returns the error for a file whose format requires a feature which is not enabled.
+#[allow(dead_code)]
+fn unsupported(path: &Path, format: FileFormat, feature: &str) -> Error {
+    Error::parse(
+        path,
+        format!("reading {format:?} files requires the `{feature}` feature"),
+    )
+}
+
+impl Handler for ConfigFileHandler {
+    /// Retrieves a value for the specified key from the configuration file.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the value associated with the key, or `None` if the key is not
+    /// found or the file could not be read or parsed.
+    fn handle_request(&self, key: &str) -> Option<String> {
+        self.try_handle_request(key).ok().flatten()
+    }
+
+    /// Retrieves a value for the specified key from the configuration file, reporting read and
+    /// parse failures.
+    ///
+    /// A file that does not exist, or that does not contain the key, is treated as a missing
+    /// value. A file that cannot be read is returned as an [`Error::Io`] and a file that cannot
+    /// be parsed, or whose format is not supported, is returned as an [`Error::Parse`].
+    fn try_handle_request(&self, key: &str) -> Result<Option<String>, Error> {
+        match self.handler()? {
+            Some(handler) => handler.try_handle_request(key),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieves a value for the specified key, reporting the [`Source::FileKey`] it came from.
+    fn handle_request_with_source(&self, key: &str) -> Option<(String, Source)> {
+        self.try_handle_request_with_source(key).ok().flatten()
+    }
+
+    /// Retrieves a value for the specified key, reporting the [`Source::FileKey`] it came from
+    /// and read and parse failures.
+    fn try_handle_request_with_source(&self, key: &str) -> Result<Option<(String, Source)>, Error> {
+        match self.handler()? {
+            Some(handler) => handler.try_handle_request_with_source(key),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieves all values for the specified key from the configuration file.
+    fn handle_request_all(&self, key: &str) -> Option<Vec<String>> {
+        self.try_handle_request_all(key).ok().flatten()
+    }
+
+    /// Retrieves all values for the specified key from the configuration file, reporting read
+    /// and parse failures.
+    fn try_handle_request_all(&self, key: &str) -> Result<Option<Vec<String>>, Error> {
+        match self.handler()? {
+            Some(handler) => handler.try_handle_request_all(key),
+            None => Ok(None),
+        }
+    }
+
+    /// Enumerates all keys within the configuration file.
+    fn keys(&self) -> Option<Vec<String>> {
+        match self.handler() {
+            Ok(Some(handler)) => handler.keys(),
+            _ => Some(Vec::new()),
+        }
+    }
+
+    fn error_policy(&self) -> ErrorPolicy {
+        self.error_policy
+    }
+}
+
+impl<'a> From<ConfigFileHandler> for Box<dyn Handler + 'a> {
+    fn from(handler: ConfigFileHandler) -> Self {
+        Box::new(handler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn config_file(dir: &TempDir, name: &str, content: &str) -> PathBuf {
+        let path = dir.path().join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_detects_format_from_extension() {
+        let dir = TempDir::new().unwrap();
+        let cases = [
+            ("app.json", r#"{"db": {"host": "json"}}"#, "db.host", "json"),
+            ("app.ini", "[db]\nhost = ini\n", "db.host", "ini"),
+            (".env", "DB_HOST=dotenv\n", "DB_HOST", "dotenv"),
+            (
+                "app.properties",
+                "db.host=properties\n",
+                "db.host",
+                "properties",
+            ),
+        ];
+        for (name, content, key, expected) in cases {
+            let handler = ConfigFileHandler::new(config_file(&dir, name, content));
+            assert_eq!(handler.handle_request(key), Some(expected.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_sniffs_format_from_content() {
+        let dir = TempDir::new().unwrap();
+        let cases = [
+            (
+                "json.conf",
+                r#"{"db": {"host": "json"}}"#,
+                "db.host",
+                "json",
+            ),
+            (
+                "ini.conf",
+                "; comment\n[db]\nhost = ini\n",
+                "db.host",
+                "ini",
+            ),
+            (
+                "dotenv.conf",
+                "export DB_HOST=dotenv\n",
+                "DB_HOST",
+                "dotenv",
+            ),
+            (
+                "properties.conf",
+                "db.host=properties\n",
+                "db.host",
+                "properties",
+            ),
+        ];
+        for (name, content, key, expected) in cases {
+            let handler = ConfigFileHandler::new(config_file(&dir, name, content));
+            assert_eq!(handler.handle_request(key), Some(expected.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_uses_explicit_format() {
+        let dir = TempDir::new().unwrap();
+        let path = config_file(&dir, "app.txt", "HOST=localhost\n");
+        let handler = ConfigFileHandler::new(path).format(FileFormat::Properties);
+        assert_eq!(
+            handler.handle_request("HOST"),
+            Some("localhost".to_string())
+        );
+    }
+
+    #[test]
+    fn test_picks_first_existing_candidate() {
+        let dir = TempDir::new().unwrap();
+        config_file(&dir, "app.json", r#"{"name": "json"}"#);
+        config_file(&dir, "app.ini", "name = ini\n");
+        let handler =
+            ConfigFileHandler::candidates(dir.path().join("app"), &["toml", "ini", "json"]);
+        assert_eq!(handler.handle_request("name"), Some("ini".to_string()));
+    }
+
+    #[test]
+    fn test_revalidate_checks_candidates_again() {
+        let dir = TempDir::new().unwrap();
+        config_file(&dir, "app.ini", "name = ini\n");
+        let cached = ConfigFileHandler::candidates(dir.path().join("app"), &["json", "ini"]);
+        let handler = ConfigFileHandler::candidates(dir.path().join("app"), &["json", "ini"])
+            .revalidate(true);
+        assert_eq!(cached.handle_request("name"), Some("ini".to_string()));
+        assert_eq!(handler.handle_request("name"), Some("ini".to_string()));
+
+        let json = config_file(&dir, "app.json", r#"{"name": "json"}"#);
+        assert_eq!(cached.handle_request("name"), Some("ini".to_string()));
+        assert_eq!(handler.handle_request("name"), Some("json".to_string()));
+
+        fs::remove_file(json).unwrap();
+        assert_eq!(handler.handle_request("name"), Some("ini".to_string()));
+    }
+
+    #[test]
+    fn test_returns_none_without_existing_candidate() {
+        let dir = TempDir::new().unwrap();
+        let handler = ConfigFileHandler::candidates(dir.path().join("app"), &["json", "ini"]);
+        assert_eq!(handler.try_handle_request("name").unwrap(), None);
+        assert_eq!(handler.keys(), Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_reports_source_of_detected_format() {
+        let dir = TempDir::new().unwrap();
+        let path = config_file(&dir, "app.json", r#"{"db": {"host": "localhost"}}"#);
+        let handler = ConfigFileHandler::new(&path);
+        let expected = Source::FileKey {
+            format: "JSON",
+            path,
+            key: "db.host".to_string(),
+        };
+        assert_eq!(
+            handler.handle_request_with_source("host"),
+            Some(("localhost".to_string(), expected))
+        );
+    }
+
+    #[test]
+    fn test_try_with_source_and_all_report_parse_error() {
+        let dir = TempDir::new().unwrap();
+        let handler = ConfigFileHandler::new(config_file(&dir, "app.json", "{"));
+        assert!(matches!(
+            handler.try_handle_request_with_source("name"),
+            Err(Error::Parse { .. })
+        ));
+        assert!(matches!(
+            handler.try_handle_request_all("name"),
+            Err(Error::Parse { .. })
+        ));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_detects_toml() {
+        let dir = TempDir::new().unwrap();
+        let handler = ConfigFileHandler::new(config_file(&dir, "app.toml", "[db]\nport = 1\n"));
+        assert_eq!(handler.handle_request("db.port"), Some("1".to_string()));
+
+        let handler = ConfigFileHandler::new(config_file(&dir, "toml.conf", "[db]\nport = 2\n"));
+        assert_eq!(handler.handle_request("db.port"), Some("2".to_string()));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_sniffs_ini_and_dotenv_before_toml() {
+        assert_eq!(FileFormat::sniff("PORT=5432\n"), FileFormat::Dotenv);
+        assert_eq!(
+            FileFormat::sniff("[db]\nhost = localhost\n"),
+            FileFormat::Ini
+        );
+        assert_eq!(FileFormat::sniff("[db]\nhost: db\n"), FileFormat::Ini);
+        assert_eq!(FileFormat::sniff("[db]\nport = 5432\n"), FileFormat::Toml);
+        assert_eq!(
+            FileFormat::sniff("name = \"app\"\n[[servers]]\nhost = \"db\"\n"),
+            FileFormat::Toml
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_sniffs_properties_before_yaml() {
+        assert_eq!(
+            FileFormat::sniff("db.host: localhost\ndb.port=5432\n"),
+            FileFormat::Properties
+        );
+        assert_eq!(FileFormat::sniff("db:\n  port: 5432\n"), FileFormat::Yaml);
+        assert_eq!(
+            FileFormat::sniff("hosts:\n- alpha\n- beta\n"),
+            FileFormat::Yaml
+        );
+        assert_eq!(FileFormat::sniff("name: \"app\"\n"), FileFormat::Yaml);
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_sniffs_flat_yaml() {
+        assert_eq!(
+            FileFormat::sniff("host: localhost\nport: 5432\n"),
+            FileFormat::Yaml
+        );
+        assert_eq!(FileFormat::sniff("host: 'x'\n"), FileFormat::Yaml);
+        assert_eq!(
+            FileFormat::sniff("host:localhost\nport: 5432\n"),
+            FileFormat::Properties
+        );
+    }
+
+    #[cfg(not(feature = "toml"))]
+    #[test]
+    fn test_reports_unsupported_format() {
+        let dir = TempDir::new().unwrap();
+        let handler = ConfigFileHandler::new(config_file(&dir, "app.toml", "[db]\nport = 1\n"));
+        match handler.try_handle_request("db.port") {
+            Err(Error::Parse { reason, .. }) => assert!(reason.contains("`toml`"), "{reason}"),
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_detects_yaml() {
+        let dir = TempDir::new().unwrap();
+        let handler = ConfigFileHandler::new(config_file(&dir, "app.yml", "db:\n  port: 1\n"));
+        assert_eq!(handler.handle_request("db.port"), Some("1".to_string()));
+
+        let handler = ConfigFileHandler::new(config_file(&dir, "yaml.conf", "db:\n  port: 2\n"));
+        assert_eq!(handler.handle_request("db.port"), Some("2".to_string()));
+    }
+}
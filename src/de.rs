@@ -0,0 +1,565 @@
+//! Deserialization of whole settings structs from a [`Handler`].
+//!
+//! See [`from_handler`] for details.
+
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use std::cell::OnceCell;
+use std::str::FromStr;
+
+use super::{Error, Handler};
+
+/// Deserializes an instance of `T` by looking up each of its fields through `handler`.
+///
+/// Each struct field is requested by its name. Fields of nested structs are requested by their
+/// dotted path, e.g. the field `port` of a field `server` is requested as `server.port`. Values are
+/// parsed according to the type of the field:
+///
+/// * numbers, booleans and characters are parsed from the value using [`FromStr`],
+/// * `Option` fields are `None` if the handler has no value for the key,
+/// * sequences are read using [`Handler::try_handle_request_all`],
+/// * maps are read using the keys enumerated by [`Handler::keys`],
+/// * unit enum variants are matched by name.
+///
+/// A field without a value is reported as missing to serde, so fields marked `#[serde(default)]`
+/// take their default value. Missing required fields produce an [`Error::MissingKey`] naming the
+/// full key.
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{Chain, DefaultHandler, EnvHandler};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct AppConfig {
+///     port: u16,
+///     verbose: Option<bool>,
+/// }
+///
+/// std::env::set_var("MYAPP_port", "8080");
+/// let handler = Chain::new().with(EnvHandler::new().prefix("MYAPP_"));
+/// let config: AppConfig = cor_args::from_handler(&handler).unwrap();
+/// assert_eq!(config.port, 8080);
+/// assert_eq!(config.verbose, None);
+/// ```
+pub fn from_handler<T, H>(handler: &H) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+    H: Handler + ?Sized,
+{
+    let context = Context {
+        handler,
+        keys: OnceCell::new(),
+    };
+    T::deserialize(KeyDeserializer {
+        context: &context,
+        key: String::new(),
+    })
+}
+
+/// State shared by all deserializers created for a single call to [`from_handler`].
+struct Context<'h, H: ?Sized> {
+    /// The handler values are looked up through.
+    handler: &'h H,
+    /// The keys enumerated by the handler, computed on first use.
+    keys: OnceCell<Option<Vec<String>>>,
+}
+
+impl<'h, H: Handler + ?Sized> Context<'h, H> {
+    /// Returns the keys enumerated by the handler.
+    fn keys(&self) -> Option<&[String]> {
+        self.keys.get_or_init(|| self.handler.keys()).as_deref()
+    }
+
+    /// Returns `true` if the handler knows any key nested below `key`.
+    fn has_nested(&self, key: &str) -> bool {
+        let prefix = format!("{key}.");
+        self.keys()
+            .is_some_and(|keys| keys.iter().any(|k| k.starts_with(&prefix)))
+    }
+
+    /// Returns `true` if the handler has a value for `key`, or for any key nested below it.
+    fn contains(&self, key: &str) -> Result<bool, Error> {
+        Ok(self.handler.try_handle_request(key)?.is_some() || self.has_nested(key))
+    }
+
+    /// Returns the names of the keys directly below `prefix`, or all top-level keys if `prefix` is
+    /// empty.
+    fn children(&self, prefix: &str) -> Vec<String> {
+        let mut children: Vec<String> = Vec::new();
+        for key in self.keys().unwrap_or_default() {
+            let rest = if prefix.is_empty() {
+                Some(key.as_str())
+            } else {
+                key.strip_prefix(prefix)
+                    .and_then(|rest| rest.strip_prefix('.'))
+            };
+            if let Some(child) = rest.and_then(|rest| rest.split('.').next()) {
+                if !child.is_empty() && !children.iter().any(|c| c == child) {
+                    children.push(child.to_string());
+                }
+            }
+        }
+        children
+    }
+}
+
+/// Joins a key onto a prefix using a `.`, e.g. `server` and `port` into `server.port`.
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+/// Prefixes the key of an [`Error::MissingKey`] reported by serde for a field of a nested struct.
+///
+/// Serde reports missing fields by their name only, while keys reported by this module are
+/// already full, dotted keys. Only keys without a `.` are therefore prefixed.
+fn qualify(error: Error, prefix: &str) -> Error {
+    match error {
+        Error::MissingKey { key } if !prefix.is_empty() && !key.contains('.') => {
+            Error::MissingKey {
+                key: join(prefix, &key),
+            }
+        }
+        error => error,
+    }
+}
+
+/// Deserializes the value of a single key, looking it up through the handler.
+struct KeyDeserializer<'c, 'h, H: ?Sized> {
+    context: &'c Context<'h, H>,
+    /// The full, dotted key. Empty for the root.
+    key: String,
+}
+
+impl<'c, 'h, H: Handler + ?Sized> KeyDeserializer<'c, 'h, H> {
+    /// Looks up the value of the key, failing if there is none.
+    fn value(&self) -> Result<ValueDeserializer, Error> {
+        match self.context.handler.try_handle_request(&self.key)? {
+            Some(value) => Ok(ValueDeserializer {
+                key: self.key.clone(),
+                value,
+            }),
+            None => Err(Error::MissingKey {
+                key: self.key.clone(),
+            }),
+        }
+    }
+}
+
+macro_rules! forward_to_value {
+    ($($method:ident)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            self.value()?.$method(visitor)
+        }
+    )*};
+}
+
+impl<'de, 'c, 'h, H: Handler + ?Sized> de::Deserializer<'de> for KeyDeserializer<'c, 'h, H> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.key.is_empty() || self.context.has_nested(&self.key) {
+            self.deserialize_map(visitor)
+        } else {
+            self.value()?.deserialize_any(visitor)
+        }
+    }
+
+    forward_to_value! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_identifier
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.context.contains(&self.key)? {
+            visitor.visit_some(self)
+        } else {
+            visitor.visit_none()
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let values = match self.context.handler.try_handle_request_all(&self.key)? {
+            Some(values) => values,
+            None => return Err(Error::MissingKey { key: self.key }),
+        };
+        let key = self.key;
+        let elements = values
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| ValueDeserializer {
+                key: join(&key, &index.to_string()),
+                value,
+            });
+        visitor.visit_seq(de::value::SeqDeserializer::new(elements))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let fields = self.context.children(&self.key);
+        let access = FieldAccess {
+            context: self.context,
+            prefix: &self.key,
+            fields: fields.into_iter(),
+            current: None,
+        };
+        visitor.visit_map(access).map_err(|e| qualify(e, &self.key))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let mut present = Vec::new();
+        for field in fields {
+            if self.context.contains(&join(&self.key, field))? {
+                present.push(field.to_string());
+            }
+        }
+        let access = FieldAccess {
+            context: self.context,
+            prefix: &self.key,
+            fields: present.into_iter(),
+            current: None,
+        };
+        visitor.visit_map(access).map_err(|e| qualify(e, &self.key))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.value()?.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+}
+
+/// Yields the fields of a struct or map, deserializing each value from its own key.
+struct FieldAccess<'c, 'h, 'p, H: ?Sized> {
+    context: &'c Context<'h, H>,
+    /// The key of the struct or map itself.
+    prefix: &'p str,
+    /// The remaining fields to yield.
+    fields: std::vec::IntoIter<String>,
+    /// The full key of the field most recently yielded.
+    current: Option<String>,
+}
+
+impl<'de, 'c, 'h, 'p, H: Handler + ?Sized> de::MapAccess<'de> for FieldAccess<'c, 'h, 'p, H> {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.fields.next() {
+            Some(field) => {
+                self.current = Some(join(self.prefix, &field));
+                seed.deserialize(field.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let key = self
+            .current
+            .take()
+            .ok_or_else(|| Error::Message("value requested before key".to_string()))?;
+        seed.deserialize(KeyDeserializer {
+            context: self.context,
+            key,
+        })
+    }
+}
+
+/// Deserializes a single value which has already been looked up.
+struct ValueDeserializer {
+    /// The key the value was found at, used to report errors.
+    key: String,
+    /// The raw value.
+    value: String,
+}
+
+impl ValueDeserializer {
+    /// Parses the value into `T`, reporting an [`Error::InvalidValue`] on failure.
+    fn parse<T>(self) -> Result<T, Error>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        self.value
+            .trim()
+            .parse::<T>()
+            .map_err(|e| Error::invalid_value::<T>(&self.key, self.value.clone(), e))
+    }
+
+    /// Parses the value as a boolean, accepting the common spellings used by environment variables.
+    fn parse_bool(self) -> Result<bool, Error> {
+        match self.value.trim().to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Ok(true),
+            "false" | "no" | "off" | "0" => Ok(false),
+            _ => Err(Error::invalid_value::<bool>(
+                &self.key,
+                self.value,
+                "expected one of true, false, yes, no, on, off, 1 or 0",
+            )),
+        }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for ValueDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.$visit(self.parse()?)
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.value)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_bool(self.parse_bool()?)
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let key = self.key;
+        let variant: de::value::StringDeserializer<Error> = self.value.into_deserializer();
+        visitor.visit_enum(variant).map_err(|e| match e {
+            Error::Message(reason) => Error::Message(format!("key `{key}`: {reason}")),
+            e => e,
+        })
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Error::MissingKey {
+            key: field.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use std::collections::HashMap;
+    use std::env;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    use super::*;
+    use crate::{Chain, DefaultHandler, EnvHandler, JSONFileHandler};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Level {
+        Debug,
+        Info,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct AppConfig {
+        server: Server,
+        verbose: bool,
+        level: Level,
+        timeout: Option<f64>,
+        include: Vec<String>,
+        #[serde(default)]
+        retries: u8,
+    }
+
+    #[test]
+    fn test_deserializes_struct() {
+        env::set_var("DE_TEST_server.host", "localhost");
+        env::set_var("DE_TEST_server.port", "8080");
+        env::set_var("DE_TEST_verbose", "yes");
+        env::set_var("DE_TEST_level", "debug");
+        env::set_var("DE_TEST_include", "a,b");
+        let handler = EnvHandler::new().prefix("DE_TEST_").delimiter(",");
+        let actual: AppConfig = from_handler(&handler).unwrap();
+        let expected = AppConfig {
+            server: Server {
+                host: "localhost".to_string(),
+                port: 8080,
+            },
+            verbose: true,
+            level: Level::Debug,
+            timeout: None,
+            include: vec!["a".to_string(), "b".to_string()],
+            retries: 0,
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_reports_missing_nested_key() {
+        env::set_var("DE_MISSING_TEST_server.host", "localhost");
+        let handler = EnvHandler::new().prefix("DE_MISSING_TEST_");
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Wrapper {
+            server: Server,
+        }
+        let actual = from_handler::<Wrapper, _>(&handler);
+        assert!(matches!(actual, Err(Error::MissingKey { key }) if key == "server.port"));
+    }
+
+    #[test]
+    fn test_reports_invalid_value() {
+        let handler = DefaultHandler::new("not_a_number");
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Port {
+            port: u16,
+        }
+        match from_handler::<Port, _>(&handler) {
+            Err(Error::InvalidValue {
+                key,
+                value,
+                expected,
+                ..
+            }) => {
+                assert_eq!(key, "port");
+                assert_eq!(value, "not_a_number");
+                assert_eq!(expected, "u16");
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_reports_parse_error_for_sequence() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "not json").unwrap();
+        let handler = JSONFileHandler::new(temp_file.path());
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Include {
+            include: Vec<String>,
+        }
+        let actual = from_handler::<Include, _>(&handler);
+        assert!(matches!(actual, Err(Error::Parse { .. })), "{actual:?}");
+    }
+
+    #[test]
+    fn test_deserializes_map_from_keys() {
+        env::set_var("DE_MAP_TEST_a", "1");
+        env::set_var("DE_MAP_TEST_b", "2");
+        let handler = Chain::new().with(EnvHandler::new().prefix("DE_MAP_TEST_"));
+        let actual: HashMap<String, u8> = from_handler(&handler).unwrap();
+        let expected = HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)]);
+        assert_eq!(actual, expected);
+    }
+}
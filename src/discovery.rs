@@ -0,0 +1,468 @@
+//! Discovery of configuration files in standard locations.
+//!
+//! See [`ConfigDiscovery`] for details.
+
+use std::path::Path;
+
+use super::*;
+
+/// Finds the configuration files of an application in standard locations.
+///
+/// The following locations are searched, from the highest to the lowest priority:
+///
+/// 1. A file named `.<app>rc` in the current directory and each of its ancestors, up to the root
+///    of the filesystem, nearest first. Its format is guessed from its content.
+/// 2. `$XDG_CONFIG_HOME/<app>/`, which defaults to `$HOME/.config/<app>/`.
+/// 3. `<dir>/<app>/` for each directory in `$XDG_CONFIG_DIRS`, in order, which defaults to
+///    `/etc/xdg/<app>/`.
+/// 4. `/etc/<app>/`.
+///
+/// Within each directory, the first existing file named `config` with one of the [supported
+/// extensions](ConfigDiscovery::extensions) is read. Relative paths in the XDG environment
+/// variables are ignored, as required by the XDG Base Directory Specification. The directories
+/// can also be given with [`ConfigDiscovery::config_home`] and [`ConfigDiscovery::config_dirs`]
+/// instead of the environment.
+///
+/// Each location becomes a [`ConfigFileHandler`], so locations without a file are simply
+/// skipped, and [`ConfigDiscovery::chain`] combines them in order of priority.
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{Chain, ConfigDiscovery, DefaultHandler, EnvHandler, Handler};
+///
+/// // Prefer the environment, then any configuration file of `myapp`, then a default.
+/// let handler = Chain::new()
+///     .with(EnvHandler::new().prefix("MYAPP_"))
+///     .with(ConfigDiscovery::new("myapp").chain())
+///     .with(DefaultHandler::new("info"));
+///
+/// let value = handler.handle_request("verbosity");
+/// ```
+pub struct ConfigDiscovery {
+    /// The name of the application, used for directory and file names.
+    app: String,
+    /// The name of the configuration file within each directory, without an extension.
+    file_name: String,
+    /// The candidate extensions of the configuration file, in order of preference.
+    extensions: Vec<String>,
+    /// The directory to walk up from, or `None` if the current directory is unknown.
+    walk_up_from: Option<PathBuf>,
+    /// Whether `.<app>rc` files are searched for.
+    walk_up: bool,
+    /// The user's configuration directory, or `None` to read it from the environment.
+    config_home: Option<PathBuf>,
+    /// The system configuration directories, or `None` to read them from the environment.
+    config_dirs: Option<Vec<PathBuf>>,
+    /// How a chain treats a failure to read or parse a discovered file.
+    error_policy: ErrorPolicy,
+}
+
+impl ConfigDiscovery {
+    /// Creates a new `ConfigDiscovery` for the specified application.
+    ///
+    /// # Arguments
+    ///
+    /// * `app` - The name of the application, e.g. `myapp` for `/etc/myapp/` and `.myapprc`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::ConfigDiscovery;
+    ///
+    /// let discovery = ConfigDiscovery::new("myapp");
+    /// ```
+    #[allow(dead_code)]
+    pub fn new(app: &str) -> Self {
+        ConfigDiscovery {
+            app: app.to_string(),
+            file_name: "config".to_string(),
+            extensions: Self::default_extensions(),
+            walk_up_from: env::current_dir().ok(),
+            walk_up: true,
+            config_home: None,
+            config_dirs: None,
+            error_policy: ErrorPolicy::default(),
+        }
+    }
+
+    /// Returns the extensions of all formats supported with the enabled features.
+    fn default_extensions() -> Vec<String> {
+        let mut extensions = Vec::new();
+        #[cfg(feature = "toml")]
+        extensions.push("toml");
+        #[cfg(feature = "yaml")]
+        extensions.extend(["yaml", "yml"]);
+        extensions.push("json");
+        #[cfg(feature = "json5")]
+        extensions.push("json5");
+        extensions.extend(["ini", "properties"]);
+        extensions.into_iter().map(str::to_string).collect()
+    }
+
+    /// Sets the name of the configuration file within each directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_name` - The file name, without an extension. Defaults to `config`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::ConfigDiscovery;
+    ///
+    /// // Search for e.g. `/etc/myapp/settings.json`.
+    /// let discovery = ConfigDiscovery::new("myapp").file_name("settings");
+    /// ```
+    #[allow(dead_code)]
+    pub fn file_name(mut self, file_name: &str) -> Self {
+        self.file_name = file_name.to_string();
+        self
+    }
+
+    /// Sets the candidate extensions of the configuration file within each directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `extensions` - The extensions, without a leading `.`, in order of preference. Defaults
+    ///   to the extensions of all formats supported with the enabled features.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::ConfigDiscovery;
+    ///
+    /// let discovery = ConfigDiscovery::new("myapp").extensions(&["json", "ini"]);
+    /// ```
+    #[allow(dead_code)]
+    pub fn extensions(mut self, extensions: &[&str]) -> Self {
+        self.extensions = extensions.iter().map(|e| e.to_string()).collect();
+        self
+    }
+
+    /// Sets the directory from which `.<app>rc` files are searched for.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory to start from. Defaults to the current directory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::ConfigDiscovery;
+    ///
+    /// let discovery = ConfigDiscovery::new("myapp").walk_up_from("/path/to/project");
+    /// ```
+    #[allow(dead_code)]
+    pub fn walk_up_from<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.walk_up_from = Some(dir.into());
+        self
+    }
+
+    /// Sets whether `.<app>rc` files are searched for.
+    ///
+    /// # Arguments
+    ///
+    /// * `walk_up` - `true` to search for `.<app>rc` files, `false` to only search the standard
+    ///   configuration directories. Defaults to `true`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::ConfigDiscovery;
+    ///
+    /// let discovery = ConfigDiscovery::new("myapp").walk_up(false);
+    /// ```
+    #[allow(dead_code)]
+    pub fn walk_up(mut self, walk_up: bool) -> Self {
+        self.walk_up = walk_up;
+        self
+    }
+
+    /// Sets the user's configuration directory, instead of reading `$XDG_CONFIG_HOME` and
+    /// `$HOME`.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory containing the `<app>/` directory. It is ignored if it is
+    ///   relative, like `$XDG_CONFIG_HOME`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::ConfigDiscovery;
+    ///
+    /// // Search `/home/user/.config/myapp/` instead of the current user's directory.
+    /// let discovery = ConfigDiscovery::new("myapp").config_home("/home/user/.config");
+    /// ```
+    #[allow(dead_code)]
+    pub fn config_home<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.config_home = Some(dir.into());
+        self
+    }
+
+    /// Sets the system configuration directories, instead of reading `$XDG_CONFIG_DIRS`.
+    ///
+    /// # Arguments
+    ///
+    /// * `dirs` - The directories containing the `<app>/` directories, from the highest to the
+    ///   lowest priority. Relative directories are ignored, like in `$XDG_CONFIG_DIRS`.
+    ///   `/etc/<app>/` is searched after them regardless.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::ConfigDiscovery;
+    ///
+    /// let discovery = ConfigDiscovery::new("myapp").config_dirs(&["/opt/myapp/etc/xdg"]);
+    /// ```
+    #[allow(dead_code)]
+    pub fn config_dirs<P: AsRef<Path>>(mut self, dirs: &[P]) -> Self {
+        self.config_dirs = Some(dirs.iter().map(|dir| dir.as_ref().to_path_buf()).collect());
+        self
+    }
+
+    /// Sets how a chain treats a failure to read or parse a discovered file.
+    ///
+    /// # Arguments
+    ///
+    /// * `error_policy` - The policy applied to read and parse errors. Defaults to
+    ///   [`ErrorPolicy::Fail`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::{ConfigDiscovery, ErrorPolicy};
+    ///
+    /// let discovery = ConfigDiscovery::new("myapp").on_error(ErrorPolicy::Warn);
+    /// ```
+    #[allow(dead_code)]
+    pub fn on_error(mut self, error_policy: ErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// Returns the directories searched for a configuration file, from the highest to the lowest
+    /// priority.
+    fn search_dirs(&self) -> Vec<PathBuf> {
+        let absolute = |path: PathBuf| path.is_absolute().then_some(path);
+        let config_home = match &self.config_home {
+            Some(dir) => absolute(dir.clone()),
+            None => env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .and_then(absolute)
+                .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config"))),
+        };
+        let config_dirs: Vec<PathBuf> = match &self.config_dirs {
+            Some(dirs) => dirs.iter().cloned().filter_map(absolute).collect(),
+            None => match env::var_os("XDG_CONFIG_DIRS") {
+                Some(dirs) if !dirs.is_empty() => {
+                    env::split_paths(&dirs).filter_map(absolute).collect()
+                }
+                _ => vec![PathBuf::from("/etc/xdg")],
+            },
+        };
+        config_home
+            .into_iter()
+            .chain(config_dirs)
+            .chain([PathBuf::from("/etc")])
+            .map(|dir| dir.join(&self.app))
+            .collect()
+    }
+
+    /// Creates a handler for each location, from the highest to the lowest priority.
+    ///
+    /// The environment is read when this method is called, but the files are only looked for
+    /// once a handler receives a request.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::ConfigDiscovery;
+    ///
+    /// for handler in ConfigDiscovery::new("myapp").handlers() {
+    ///     // e.g. wrap each handler before adding it to a chain
+    /// }
+    /// ```
+    #[allow(dead_code)]
+    pub fn handlers(&self) -> Vec<ConfigFileHandler> {
+        let rc_name = format!(".{}rc", self.app);
+        let rc_files = self
+            .walk_up_from
+            .iter()
+            .filter(|_| self.walk_up)
+            .flat_map(|dir| dir.ancestors())
+            .map(|dir| ConfigFileHandler::new(dir.join(&rc_name)));
+        let extensions: Vec<&str> = self.extensions.iter().map(String::as_str).collect();
+        let config_files = self
+            .search_dirs()
+            .into_iter()
+            .map(|dir| ConfigFileHandler::candidates(dir.join(&self.file_name), &extensions));
+        rc_files
+            .chain(config_files)
+            .map(|handler| handler.on_error(self.error_policy))
+            .collect()
+    }
+
+    /// Creates a chain querying each location, from the highest to the lowest priority.
+    ///
+    /// See [`ConfigDiscovery::handlers`].
+    #[allow(dead_code)]
+    pub fn chain(&self) -> Chain<'static> {
+        self.handlers()
+            .into_iter()
+            .map(|handler| Box::new(handler) as Box<dyn Handler>)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_prefers_nearest_rc_file() {
+        let root = TempDir::new().unwrap();
+        let project = root.path().join("project");
+        let cwd = project.join("src");
+        fs::create_dir_all(&cwd).unwrap();
+        fs::write(
+            root.path().join(".discoverytestrc"),
+            r#"{"name": "root", "root_only": "yes"}"#,
+        )
+        .unwrap();
+        fs::write(project.join(".discoverytestrc"), "name = project\n").unwrap();
+
+        let handler = ConfigDiscovery::new("discoverytest")
+            .walk_up_from(&cwd)
+            .chain();
+        assert_eq!(handler.handle_request("name"), Some("project".to_string()));
+        assert_eq!(handler.handle_request("root_only"), Some("yes".to_string()));
+    }
+
+    #[test]
+    fn test_searches_xdg_directories_in_order() {
+        let root = TempDir::new().unwrap();
+        let config_home = root.path().join("home");
+        let config_dir = root.path().join("dir");
+        fs::create_dir_all(config_home.join("xdgtest")).unwrap();
+        fs::create_dir_all(config_dir.join("xdgtest")).unwrap();
+        fs::write(
+            config_home.join("xdgtest").join("config.json"),
+            r#"{"name": "home"}"#,
+        )
+        .unwrap();
+        fs::write(
+            config_dir.join("xdgtest").join("config.ini"),
+            "name = dir\nfallback = dir\n",
+        )
+        .unwrap();
+
+        let handler = ConfigDiscovery::new("xdgtest")
+            .walk_up(false)
+            .config_home(&config_home)
+            .config_dirs(&[Path::new("relative/ignored"), config_dir.as_path()])
+            .chain();
+        assert_eq!(handler.handle_request("name"), Some("home".to_string()));
+        assert_eq!(handler.handle_request("fallback"), Some("dir".to_string()));
+    }
+
+    #[test]
+    fn test_searches_etc_after_config_dirs() {
+        let discovery = ConfigDiscovery::new("etctest")
+            .config_home("/home/user/.config")
+            .config_dirs(&["/opt/xdg"]);
+        let expected = [
+            "/home/user/.config/etctest",
+            "/opt/xdg/etctest",
+            "/etc/etctest",
+        ];
+        assert_eq!(discovery.search_dirs(), expected.map(PathBuf::from));
+    }
+
+    #[test]
+    fn test_reads_xdg_directories_from_environment() {
+        // No other test sets these variables, as the environment is shared by all tests.
+        let discovery = ConfigDiscovery::new("envtest");
+        let dirs = env::join_paths(["/xdg/a", "relative", "/xdg/b"]).unwrap();
+        env::set_var("XDG_CONFIG_HOME", "/xdg/home");
+        env::set_var("XDG_CONFIG_DIRS", dirs);
+        let expected = [
+            "/xdg/home/envtest",
+            "/xdg/a/envtest",
+            "/xdg/b/envtest",
+            "/etc/envtest",
+        ];
+        assert_eq!(discovery.search_dirs(), expected.map(PathBuf::from));
+
+        env::set_var("XDG_CONFIG_HOME", "relative");
+        env::set_var("XDG_CONFIG_DIRS", "");
+        let home = env::var_os("HOME").map(|home| Path::new(&home).join(".config/envtest"));
+        let expected: Vec<PathBuf> = home
+            .into_iter()
+            .chain(["/etc/xdg/envtest", "/etc/envtest"].map(PathBuf::from))
+            .collect();
+        assert_eq!(discovery.search_dirs(), expected);
+
+        env::remove_var("XDG_CONFIG_HOME");
+        env::remove_var("XDG_CONFIG_DIRS");
+    }
+
+    #[test]
+    fn test_prefers_rc_file_over_xdg_directories() {
+        let root = TempDir::new().unwrap();
+        let config_home = root.path().join("home");
+        fs::create_dir_all(config_home.join("rctest")).unwrap();
+        fs::write(
+            config_home.join("rctest").join("config.json"),
+            r#"{"name": "home", "home_only": "yes"}"#,
+        )
+        .unwrap();
+        fs::write(root.path().join(".rctestrc"), "name = rc\n").unwrap();
+
+        let handler = ConfigDiscovery::new("rctest")
+            .walk_up_from(root.path())
+            .config_home(&config_home)
+            .config_dirs::<&Path>(&[])
+            .chain();
+        assert_eq!(handler.handle_request("name"), Some("rc".to_string()));
+        assert_eq!(handler.handle_request("home_only"), Some("yes".to_string()));
+    }
+
+    #[test]
+    fn test_orders_all_locations() {
+        let root = TempDir::new().unwrap();
+        let project = root.path().join("project");
+        let config_home = root.path().join("home");
+        let config_dirs = [root.path().join("dir_a"), root.path().join("dir_b")];
+        let files = [
+            project.join(".ordertestrc"),
+            root.path().join(".ordertestrc"),
+            config_home.join("ordertest").join("config.json"),
+            config_dirs[0].join("ordertest").join("config.json"),
+            config_dirs[1].join("ordertest").join("config.json"),
+        ];
+        // The file of each location has the keys `key0` up to its own index, so each key is
+        // answered by the location with the same index if the locations are queried in order.
+        for (index, file) in files.iter().enumerate() {
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            let entries: Vec<String> = (0..=index)
+                .map(|key| format!(r#""key{key}": "{index}""#))
+                .collect();
+            fs::write(file, format!("{{{}}}", entries.join(", "))).unwrap();
+        }
+
+        let handler = ConfigDiscovery::new("ordertest")
+            .walk_up_from(&project)
+            .config_home(&config_home)
+            .config_dirs(&config_dirs)
+            .chain();
+        for index in 0..files.len() {
+            let actual = handler.handle_request(&format!("key{index}"));
+            assert_eq!(actual, Some(index.to_string()));
+        }
+    }
+}
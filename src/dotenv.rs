@@ -0,0 +1,327 @@
+//! A handler for dotenv files.
+//!
+//! See [`DotenvHandler`] for details.
+
+use serde_json::Map;
+
+use super::*;
+
+/// A handler for retrieving values from a specified dotenv (`.env`) file.
+///
+/// Unlike [`EnvHandler`], the file is never loaded into the process environment, so several
+/// files can be placed in a chain and, e.g., `.env.local` can take priority over `.env`.
+///
+/// The file is read as a list of `KEY=value` entries:
+///
+/// * Blank lines and lines starting with `#` are ignored, as is an `export` prefix.
+/// * Unquoted values end at a `#` preceded by whitespace, and surrounding whitespace is removed.
+/// * Single-quoted values are taken literally.
+/// * Double-quoted values support the escape sequences `\n`, `\r`, `\t`, `\\`, `\"` and `\$`.
+/// * Quoted values may span several lines.
+/// * `${VAR}` within unquoted and double-quoted values is replaced by the value of the entry
+///   `VAR` defined earlier in the file, or else by the environment variable `VAR`, or else by
+///   nothing. A `${` without a closing `}` is a parse error.
+///
+/// If a key occurs more than once, the last value is used.
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{Chain, DotenvHandler, Handler};
+///
+/// // Prefer values from `.env.local` over those from `.env`.
+/// let handler = Chain::new()
+///     .with(DotenvHandler::new(".env.local"))
+///     .with(DotenvHandler::new(".env"));
+///
+/// let value = handler.handle_request("DATABASE_URL");
+/// ```
+pub struct DotenvHandler {
+    /// The dotenv file, together with the cached index of its entries.
+    tree: TreeFile,
+}
+
+impl DotenvHandler {
+    /// Creates a new `DotenvHandler` with the specified file path.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - The path to the dotenv file from which values are to be retrieved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::DotenvHandler;
+    ///
+    /// let handler = DotenvHandler::new(".env");
+    /// ```
+    #[allow(dead_code)]
+    pub fn new<P>(file_path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        DotenvHandler {
+            tree: TreeFile::new(file_path, "dotenv"),
+        }
+    }
+
+    /// Parses the content of the file into an object mapping each key to its value.
+    fn parse_content(&self, content: &str) -> Result<Value, String> {
+        let mut entries = Map::new();
+        let mut lines = content.lines().enumerate();
+        while let Some((index, line)) = lines.next() {
+            let line_number = index + 1;
+            let line = line.trim_start();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line
+                .strip_prefix("export")
+                .filter(|rest| rest.starts_with(char::is_whitespace))
+                .map_or(line, str::trim_start);
+            let Some((key, rest)) = line.split_once('=') else {
+                return Err(format!("expected `KEY=value` at line {line_number}"));
+            };
+            let key = key.trim();
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return Err(format!("invalid key `{key}` at line {line_number}"));
+            }
+            let rest = rest.trim_start();
+            let value = match rest.chars().next() {
+                Some(quote @ ('\'' | '"')) => {
+                    let mut raw = rest[1..].to_string();
+                    let end = loop {
+                        if let Some(end) = closing_quote(&raw, quote) {
+                            break end;
+                        }
+                        let Some((_, next)) = lines.next() else {
+                            return Err(format!(
+                                "unterminated quoted value starting at line {line_number}"
+                            ));
+                        };
+                        raw.push('\n');
+                        raw.push_str(next);
+                    };
+                    let trailing = raw[end + 1..].trim_start();
+                    if !trailing.is_empty() && !trailing.starts_with('#') {
+                        return Err(format!(
+                            "unexpected `{trailing}` after the quoted value starting at line \
+                             {line_number}"
+                        ));
+                    }
+                    if quote == '\'' {
+                        Ok(raw[..end].to_string())
+                    } else {
+                        interpolate(&raw[..end], true, &entries)
+                    }
+                }
+                _ => interpolate(strip_inline_comment(rest), false, &entries),
+            }
+            .map_err(|reason| format!("{reason} at line {line_number}"))?;
+            entries.insert(key.to_string(), Value::String(value));
+        }
+        Ok(Value::Object(entries))
+    }
+}
+
+tree_file_handler!(DotenvHandler, ".env", impl TreeFormat);
+
+impl TreeFormat for DotenvHandler {
+    /// Resolves a key by its exact name.
+    fn lookup<'v>(
+        &self,
+        _tree: &TreeFile,
+        index: &'v TreeIndex,
+        key: &str,
+    ) -> Result<Option<(&'v Value, String)>, Error> {
+        Ok(index.entry(key))
+    }
+}
+
+/// Returns the position of the quote ending a quoted value, skipping escaped double quotes.
+fn closing_quote(raw: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in raw.char_indices() {
+        match c {
+            '\\' if quote == '"' && !escaped => escaped = true,
+            c if c == quote && !escaped => return Some(i),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+/// Removes an inline comment, which starts with whitespace followed by `#`, and surrounding
+/// whitespace from an unquoted value.
+fn strip_inline_comment(value: &str) -> &str {
+    let end = value
+        .char_indices()
+        .find(|&(i, c)| c == '#' && value[..i].ends_with(char::is_whitespace))
+        .map_or(value.len(), |(i, _)| i);
+    value[..end].trim()
+}
+
+/// Replaces `${VAR}` references and, if `escapes` is set, escape sequences within a value.
+///
+/// # Returns
+///
+/// The value, or the reason it is malformed if a reference is not terminated.
+fn interpolate(text: &str, escapes: bool, entries: &Map<String, Value>) -> Result<String, String> {
+    let mut value = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if escapes => match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('r') => value.push('\r'),
+                Some('t') => value.push('\t'),
+                Some(c @ ('\\' | '"' | '$')) => value.push(c),
+                Some(c) => {
+                    value.push('\\');
+                    value.push(c);
+                }
+                None => value.push('\\'),
+            },
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(format!("unterminated `${{{name}` reference")),
+                    }
+                }
+                match entries.get(&name).and_then(Value::as_str) {
+                    Some(referenced) => value.push_str(referenced),
+                    None => value.push_str(&env::var(&name).unwrap_or_default()),
+                }
+            }
+            c => value.push(c),
+        }
+    }
+    Ok(value)
+}
+
+impl<'a> From<DotenvHandler> for Box<dyn Handler + 'a> {
+    fn from(handler: DotenvHandler) -> Self {
+        Box::new(handler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    fn dotenv_file(content: &str) -> NamedTempFile {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(temp_file, "{content}").unwrap();
+        temp_file
+    }
+
+    #[test]
+    fn test_retrieves_unquoted_value() {
+        let temp_file =
+            dotenv_file("# database\nexport HOST = localhost  # the primary\nPORT=5432\n");
+        let handler = DotenvHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("HOST"),
+            Some("localhost".to_string())
+        );
+        assert_eq!(handler.handle_request("PORT"), Some("5432".to_string()));
+    }
+
+    #[test]
+    fn test_retrieves_quoted_values() {
+        let temp_file = dotenv_file(
+            "SINGLE='a \\n ${PORT} # b'\nDOUBLE=\"say \\\"hi\\\"\\tnow\" # comment\nHASH=a#b\n",
+        );
+        let handler = DotenvHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("SINGLE"),
+            Some("a \\n ${PORT} # b".to_string())
+        );
+        assert_eq!(
+            handler.handle_request("DOUBLE"),
+            Some("say \"hi\"\tnow".to_string())
+        );
+        assert_eq!(handler.handle_request("HASH"), Some("a#b".to_string()));
+    }
+
+    #[test]
+    fn test_retrieves_multi_line_value() {
+        let temp_file = dotenv_file("KEY=\"-----BEGIN-----\nabc\n-----END-----\"\nNEXT=1\n");
+        let handler = DotenvHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("KEY"),
+            Some("-----BEGIN-----\nabc\n-----END-----".to_string())
+        );
+        assert_eq!(handler.handle_request("NEXT"), Some("1".to_string()));
+    }
+
+    #[test]
+    fn test_expands_references() {
+        let temp_file = dotenv_file(
+            "HOST=localhost\nURL=http://${HOST}:${DOTENV_TEST_MISSING}/\nLITERAL=\"\\${HOST}\"\n",
+        );
+        let handler = DotenvHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("URL"),
+            Some("http://localhost:/".to_string())
+        );
+        assert_eq!(
+            handler.handle_request("LITERAL"),
+            Some("${HOST}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_later_file_is_overridden_in_chain() {
+        let local = dotenv_file("LEVEL=debug\n");
+        let shared = dotenv_file("LEVEL=info\nNAME=example\n");
+        let handler = Chain::new()
+            .with(DotenvHandler::new(local.path()))
+            .with(DotenvHandler::new(shared.path()));
+        assert_eq!(handler.handle_request("LEVEL"), Some("debug".to_string()));
+        assert_eq!(handler.handle_request("NAME"), Some("example".to_string()));
+    }
+
+    #[test]
+    fn test_does_not_modify_environment() {
+        let temp_file = dotenv_file("DOTENV_TEST_UNSET=value\n");
+        let handler = DotenvHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("DOTENV_TEST_UNSET"),
+            Some("value".to_string())
+        );
+        assert!(env::var("DOTENV_TEST_UNSET").is_err());
+    }
+
+    #[test]
+    fn test_reports_unterminated_quote() {
+        let temp_file = dotenv_file("A=1\nKEY=\"never closed\n");
+        let handler = DotenvHandler::new(temp_file.path());
+        match handler.try_handle_request("A") {
+            Err(Error::Parse { reason, .. }) => assert!(reason.contains("line 2"), "{reason}"),
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_reports_unterminated_reference() {
+        for content in ["A=1\nKEY=${A\n", "A=1\nKEY=\"${A\"\n"] {
+            let temp_file = dotenv_file(content);
+            let handler = DotenvHandler::new(temp_file.path());
+            match handler.try_handle_request("A") {
+                Err(Error::Parse { reason, .. }) => {
+                    assert!(reason.contains("`${A`"), "{reason}");
+                    assert!(reason.contains("line 2"), "{reason}");
+                }
+                other => panic!("expected a parse error, got {other:?}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,469 @@
+//! A handler for drop-in configuration directories.
+//!
+//! See [`DropInDirHandler`] for details.
+
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::RwLock;
+
+use super::*;
+
+/// The path and handler of each drop-in file.
+type Layers = Vec<(PathBuf, Arc<ConfigFileHandler>)>;
+
+/// A handler for retrieving values from a drop-in directory, such as `/etc/<app>/conf.d/`.
+///
+/// Every non-hidden file in the directory whose format is recognized by
+/// [`FileFormat::from_path`] is a layer, read by a [`ConfigFileHandler`]. The layers are applied
+/// in lexical order of their file names, so later files override earlier ones: a request is
+/// answered by the last file, e.g. `99-local.json` rather than `10-defaults.json`, which
+/// defines the key.
+///
+/// The directory is listed once, on the first request, unless
+/// [revalidation](DropInDirHandler::revalidate) is enabled. A directory that does not exist is
+/// treated as empty.
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{DropInDirHandler, Handler};
+///
+/// // Create a new DropInDirHandler for the JSON drop-ins of `myapp`
+/// let handler = DropInDirHandler::new("/etc/myapp/conf.d").extensions(&["json"]);
+///
+/// // Handle a configuration request matching a `"some_key"` within the drop-ins
+/// let value = handler.handle_request("some_key");
+/// ```
+pub struct DropInDirHandler {
+    /// The path of the drop-in directory.
+    dir_path: PathBuf,
+    /// The extensions of the files which are read, or `None` to read every supported file.
+    extensions: Option<Vec<String>>,
+    /// Whether bare keys which occur more than once within a file are reported as ambiguous.
+    strict: bool,
+    /// Whether the directory is listed and its files are parsed again when they change.
+    revalidate: bool,
+    /// How a chain treats a failure to read the directory or to read or parse a file.
+    error_policy: ErrorPolicy,
+    /// The path and handler of each layer, in lexical order, once the directory has been listed.
+    layers: RwLock<Option<Arc<Layers>>>,
+}
+
+impl DropInDirHandler {
+    /// Creates a new `DropInDirHandler` with the specified directory path.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir_path` - The path to the directory containing the drop-in files.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::DropInDirHandler;
+    ///
+    /// let handler = DropInDirHandler::new("/etc/myapp/conf.d");
+    /// ```
+    #[allow(dead_code)]
+    pub fn new<P>(dir_path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        DropInDirHandler {
+            dir_path: dir_path.into(),
+            extensions: None,
+            strict: false,
+            revalidate: false,
+            error_policy: ErrorPolicy::default(),
+            layers: RwLock::new(None),
+        }
+    }
+
+    /// Restricts the files which are read to those with one of the specified extensions.
+    ///
+    /// # Arguments
+    ///
+    /// * `extensions` - The extensions, without a leading `.`, ignoring case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::DropInDirHandler;
+    ///
+    /// // Only read `/etc/myapp/conf.d/*.json`.
+    /// let handler = DropInDirHandler::new("/etc/myapp/conf.d").extensions(&["json"]);
+    /// ```
+    #[allow(dead_code)]
+    pub fn extensions(mut self, extensions: &[&str]) -> Self {
+        self.extensions = Some(extensions.iter().map(|e| e.to_lowercase()).collect());
+        self
+    }
+
+    /// Sets whether bare keys which occur more than once within a file are reported as
+    /// ambiguous.
+    ///
+    /// See [`ConfigFileHandler::strict`].
+    ///
+    /// # Arguments
+    ///
+    /// * `strict` - `true` to report ambiguous keys, `false` to return the first match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::DropInDirHandler;
+    ///
+    /// let handler = DropInDirHandler::new("/etc/myapp/conf.d").strict(true);
+    /// ```
+    #[allow(dead_code)]
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets whether added, removed and changed files are picked up.
+    ///
+    /// With revalidation enabled, the directory is listed again on every request, and each file
+    /// is parsed again if it changed, as described for [`JSONFileHandler::revalidate`].
+    ///
+    /// # Arguments
+    ///
+    /// * `revalidate` - `true` to pick up changes, `false` to list the directory and parse each
+    ///   file only once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::DropInDirHandler;
+    ///
+    /// let handler = DropInDirHandler::new("/etc/myapp/conf.d").revalidate(true);
+    /// ```
+    #[allow(dead_code)]
+    pub fn revalidate(mut self, revalidate: bool) -> Self {
+        self.revalidate = revalidate;
+        self
+    }
+
+    /// Sets how a chain treats a failure to read the directory or to read or parse a file.
+    ///
+    /// A file which cannot be read or parsed fails the whole directory, rather than being
+    /// skipped, so a broken drop-in does not silently change which values apply.
+    ///
+    /// # Arguments
+    ///
+    /// * `error_policy` - The policy applied to read and parse errors. Defaults to
+    ///   [`ErrorPolicy::Fail`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::{DropInDirHandler, ErrorPolicy};
+    ///
+    /// let handler = DropInDirHandler::new("/etc/myapp/conf.d").on_error(ErrorPolicy::Warn);
+    /// ```
+    #[allow(dead_code)]
+    pub fn on_error(mut self, error_policy: ErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// Returns whether a file in the directory is a drop-in.
+    fn is_drop_in(&self, path: &Path) -> bool {
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        let selected = match (&self.extensions, extension) {
+            (Some(extensions), Some(extension)) => extensions.contains(&extension),
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        !hidden && selected && path.is_file() && FileFormat::from_path(path).is_some()
+    }
+
+    /// Returns the layers, in lexical order, listing the directory if necessary.
+    ///
+    /// When the directory is listed again, the handlers of files which are still present are
+    /// reused.
+    ///
+    /// # Returns
+    ///
+    /// The layers, which are empty if the directory does not exist, or an [`Error::Io`] if the
+    /// directory could not be listed.
+    fn layers(&self) -> Result<Arc<Layers>, Error> {
+        let cached = self.layers.read().unwrap().clone();
+        if let Some(layers) = cached.as_ref() {
+            if !self.revalidate {
+                return Ok(Arc::clone(layers));
+            }
+        }
+        let mut paths = match fs::read_dir(&self.dir_path) {
+            Ok(entries) => entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| Error::io(&self.dir_path, e))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(Error::io(&self.dir_path, e)),
+        };
+        paths.retain(|path| self.is_drop_in(path));
+        paths.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+        let layers: Layers = paths
+            .into_iter()
+            .map(|path| {
+                let reused = cached
+                    .iter()
+                    .flat_map(|layers| layers.iter())
+                    .find(|(layer_path, _)| *layer_path == path);
+                let handler = match reused {
+                    Some((_, handler)) => Arc::clone(handler),
+                    None => Arc::new(
+                        ConfigFileHandler::new(&path)
+                            .strict(self.strict)
+                            .revalidate(self.revalidate),
+                    ),
+                };
+                (path, handler)
+            })
+            .collect();
+        let layers = Arc::new(layers);
+        *self.layers.write().unwrap() = Some(Arc::clone(&layers));
+        Ok(layers)
+    }
+
+    /// Queries every layer with `query`, from the last to the first, in a single pass.
+    ///
+    /// Every layer is queried, even once a later one is found to define the key, so that a file
+    /// which cannot be read or parsed fails the request regardless of where it is in the order.
+    ///
+    /// # Returns
+    ///
+    /// The result of the last layer defining the key, `None` if no layer defines it, or the first
+    /// error of any layer.
+    fn find<T>(
+        &self,
+        query: impl Fn(&ConfigFileHandler) -> Result<Option<T>, Error>,
+    ) -> Result<Option<T>, Error> {
+        let mut found = None;
+        for (_, layer) in self.layers()?.iter().rev() {
+            let result = query(layer)?;
+            if found.is_none() {
+                found = result;
+            }
+        }
+        Ok(found)
+    }
+}
+
+impl Handler for DropInDirHandler {
+    /// Retrieves a value for the specified key from the last drop-in defining it.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the value associated with the key, or `None` if the key is not
+    /// found or the directory or a file could not be read or parsed.
+    fn handle_request(&self, key: &str) -> Option<String> {
+        self.try_handle_request(key).ok().flatten()
+    }
+
+    /// Retrieves a value for the specified key from the last drop-in defining it, reporting
+    /// read and parse failures.
+    ///
+    /// Every drop-in is consulted, from the last to the first, and the first failure is
+    /// returned even if a later drop-in defines the key.
+    fn try_handle_request(&self, key: &str) -> Result<Option<String>, Error> {
+        self.find(|layer| layer.try_handle_request(key))
+    }
+
+    /// Retrieves a value for the specified key, reporting the [`Source::FileKey`] of the drop-in
+    /// it came from.
+    fn handle_request_with_source(&self, key: &str) -> Option<(String, Source)> {
+        self.try_handle_request_with_source(key).ok().flatten()
+    }
+
+    /// Retrieves a value for the specified key, reporting the [`Source::FileKey`] of the drop-in
+    /// it came from and read and parse failures as described for
+    /// [`DropInDirHandler::try_handle_request`].
+    fn try_handle_request_with_source(&self, key: &str) -> Result<Option<(String, Source)>, Error> {
+        self.find(|layer| layer.try_handle_request_with_source(key))
+    }
+
+    /// Retrieves all values for the specified key from the last drop-in defining it.
+    fn handle_request_all(&self, key: &str) -> Option<Vec<String>> {
+        self.try_handle_request_all(key).ok().flatten()
+    }
+
+    /// Retrieves all values for the specified key from the last drop-in defining it, reporting
+    /// read and parse failures as described for [`DropInDirHandler::try_handle_request`].
+    fn try_handle_request_all(&self, key: &str) -> Result<Option<Vec<String>>, Error> {
+        self.find(|layer| layer.try_handle_request_all(key))
+    }
+
+    /// Enumerates the keys of all drop-ins, from the last to the first, without duplicates.
+    fn keys(&self) -> Option<Vec<String>> {
+        let mut keys = Some(Vec::new());
+        for (_, layer) in self.layers().unwrap_or_default().iter().rev() {
+            keys = merge_keys(keys, layer.keys());
+        }
+        keys
+    }
+
+    fn error_policy(&self) -> ErrorPolicy {
+        self.error_policy
+    }
+}
+
+impl<'a> From<DropInDirHandler> for Box<dyn Handler + 'a> {
+    fn from(handler: DropInDirHandler) -> Self {
+        Box::new(handler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn drop_in_dir(files: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        for (name, content) in files {
+            fs::write(dir.path().join(name), content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_later_files_override_earlier_files() {
+        let dir = drop_in_dir(&[
+            ("50-site.json", r#"{"level": "info", "name": "site"}"#),
+            ("10-defaults.json", r#"{"level": "warn", "port": 80}"#),
+            ("99-local.json", r#"{"level": "debug"}"#),
+        ]);
+        let handler = DropInDirHandler::new(dir.path());
+        assert_eq!(handler.handle_request("level"), Some("debug".to_string()));
+        assert_eq!(handler.handle_request("name"), Some("site".to_string()));
+        assert_eq!(handler.handle_request("port"), Some("80".to_string()));
+        assert_eq!(handler.handle_request("missing"), None);
+    }
+
+    #[test]
+    fn test_reads_mixed_formats() {
+        let dir = drop_in_dir(&[
+            (
+                "10-defaults.json",
+                r#"{"db": {"host": "json", "port": 5432}}"#,
+            ),
+            ("20-override.ini", "[db]\nhost = ini\n"),
+        ]);
+        let handler = DropInDirHandler::new(dir.path());
+        assert_eq!(handler.handle_request("db.host"), Some("ini".to_string()));
+        assert_eq!(handler.handle_request("db.port"), Some("5432".to_string()));
+    }
+
+    #[test]
+    fn test_skips_hidden_unsupported_and_unselected_files() {
+        let dir = drop_in_dir(&[
+            ("10-defaults.json", r#"{"level": "info"}"#),
+            (".20-hidden.json", r#"{"level": "hidden"}"#),
+            ("30-notes.txt", "level = text\n"),
+            ("40-local.ini", "level = ini\n"),
+        ]);
+        let handler = DropInDirHandler::new(dir.path());
+        assert_eq!(handler.handle_request("level"), Some("ini".to_string()));
+
+        let handler = DropInDirHandler::new(dir.path()).extensions(&["JSON"]);
+        assert_eq!(handler.handle_request("level"), Some("info".to_string()));
+    }
+
+    #[test]
+    fn test_reports_source_of_overriding_file() {
+        let dir = drop_in_dir(&[
+            ("10-defaults.json", r#"{"level": "info"}"#),
+            ("20-local.json", r#"{"level": "debug"}"#),
+        ]);
+        let handler = DropInDirHandler::new(dir.path());
+        let expected = Source::FileKey {
+            format: "JSON",
+            path: dir.path().join("20-local.json"),
+            key: "level".to_string(),
+        };
+        assert_eq!(
+            handler.handle_request_with_source("level"),
+            Some(("debug".to_string(), expected))
+        );
+    }
+
+    #[test]
+    fn test_enumerates_keys_of_all_files() {
+        let dir = drop_in_dir(&[
+            ("10-defaults.json", r#"{"a": 1, "b": 2}"#),
+            ("20-local.json", r#"{"b": 3, "c": 4}"#),
+        ]);
+        let handler = DropInDirHandler::new(dir.path());
+        let expected = vec!["b".to_string(), "c".to_string(), "a".to_string()];
+        assert_eq!(handler.keys(), Some(expected));
+    }
+
+    #[test]
+    fn test_reports_broken_file() {
+        let dir = drop_in_dir(&[
+            ("10-defaults.json", r#"{"level": "info"}"#),
+            ("20-broken.json", "{"),
+        ]);
+        let handler = DropInDirHandler::new(dir.path());
+        assert!(matches!(
+            handler.try_handle_request("level"),
+            Err(Error::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn test_reports_broken_file_overridden_by_later_file() {
+        let dir = drop_in_dir(&[
+            ("10-defaults.json", "{"),
+            ("99-local.json", r#"{"level": "debug"}"#),
+        ]);
+        let handler = DropInDirHandler::new(dir.path());
+        assert!(matches!(
+            handler.try_handle_request("level"),
+            Err(Error::Parse { .. })
+        ));
+        assert_eq!(handler.handle_request("level"), None);
+        assert_eq!(handler.handle_request_with_source("level"), None);
+        assert!(matches!(
+            handler.try_handle_request_with_source("level"),
+            Err(Error::Parse { .. })
+        ));
+        assert!(matches!(
+            handler.try_handle_request_all("level"),
+            Err(Error::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn test_revalidation_picks_up_new_files() {
+        let dir = drop_in_dir(&[("10-defaults.json", r#"{"level": "info"}"#)]);
+        let cached = DropInDirHandler::new(dir.path());
+        let revalidated = DropInDirHandler::new(dir.path()).revalidate(true);
+        assert_eq!(cached.handle_request("level"), Some("info".to_string()));
+        assert_eq!(
+            revalidated.handle_request("level"),
+            Some("info".to_string())
+        );
+
+        fs::write(dir.path().join("20-local.json"), r#"{"level": "debug"}"#).unwrap();
+        assert_eq!(cached.handle_request("level"), Some("info".to_string()));
+        assert_eq!(
+            revalidated.handle_request("level"),
+            Some("debug".to_string())
+        );
+    }
+
+    #[test]
+    fn test_returns_none_for_nonexistent_directory() {
+        let handler = DropInDirHandler::new("/nonexistent/conf.d");
+        assert_eq!(handler.try_handle_request("level").unwrap(), None);
+        assert_eq!(handler.keys(), Some(Vec::new()));
+    }
+}
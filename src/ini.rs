@@ -0,0 +1,369 @@
+//! A handler for INI files.
+//!
+//! See [`IniFileHandler`] for details.
+
+use serde_json::Map;
+
+use super::*;
+
+/// A handler for retrieving values from a specified INI file.
+///
+/// Keys within a section are requested as `section.key`, and keys before the first section
+/// header, in the global section, are requested by their bare name. Section names may contain
+/// dots themselves, so `server.alpha.port` finds `port` within `[server.alpha]`.
+///
+/// The file is read line by line:
+///
+/// * Lines starting with `;` or `#` are comments, as is anything following ` ;` or ` #` on a
+///   line outside of quotes.
+/// * Keys and values are separated by the first `=` or `:`, and surrounding whitespace is
+///   removed.
+/// * A value enclosed in double or single quotes is returned without the quotes, keeping any
+///   whitespace, `;` or `#` within them.
+/// * A key/value line ending with `\` is continued on the next line, without the next line's
+///   leading whitespace. Comment lines are never continued.
+///
+/// Section names are matched case-insensitively unless [case
+/// sensitivity](IniFileHandler::case_sensitive) is enabled, while key names are always matched
+/// case-sensitively. If a key occurs more than once within a section, the last value is used.
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{Handler, IniFileHandler};
+///
+/// // Create a new IniFileHandler specifying a path to a file.
+/// let handler = IniFileHandler::new("setup.cfg");
+///
+/// // Handle a configuration request matching `host` within the `[database]` section.
+/// let value = handler.handle_request("database.host");
+/// ```
+pub struct IniFileHandler {
+    /// The INI file, together with the cached index of its sections.
+    tree: TreeFile,
+    /// Whether section names are matched case-sensitively.
+    case_sensitive: bool,
+}
+
+impl IniFileHandler {
+    /// Creates a new `IniFileHandler` with the specified file path.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - The path to the INI file from which values are to be retrieved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::IniFileHandler;
+    ///
+    /// let handler = IniFileHandler::new("setup.cfg");
+    /// ```
+    #[allow(dead_code)]
+    pub fn new<P>(file_path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        IniFileHandler {
+            tree: TreeFile::new(file_path, "INI"),
+            case_sensitive: false,
+        }
+    }
+
+    /// Sets whether section names are matched case-sensitively.
+    ///
+    /// # Arguments
+    ///
+    /// * `case_sensitive` - `true` to distinguish `[Server]` from `[server]`. Defaults to
+    ///   `false`, in which case sections differing only in case are merged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::IniFileHandler;
+    ///
+    /// let handler = IniFileHandler::new("setup.cfg").case_sensitive(true);
+    /// ```
+    #[allow(dead_code)]
+    pub fn case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Returns the name a section is stored and looked up by.
+    fn section_name(&self, section: &str) -> String {
+        if self.case_sensitive {
+            section.to_string()
+        } else {
+            section.to_lowercase()
+        }
+    }
+
+    /// Parses the content of the file into an object mapping each section name to an object of
+    /// its keys. The global section is stored under the empty name.
+    fn parse_content(&self, content: &str) -> Result<Value, String> {
+        let mut sections = Map::new();
+        let mut section = String::new();
+        sections.insert(section.clone(), Value::Object(Map::new()));
+        let mut lines = content.lines().enumerate();
+        while let Some((index, line)) = lines.next() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+            let mut line = line.to_string();
+            while line.ends_with('\\') {
+                line.pop();
+                match lines.next() {
+                    Some((_, next)) => line.push_str(next.trim()),
+                    None => break,
+                }
+            }
+            if let Some(header) = line.strip_prefix('[') {
+                let Some(end) = header.find(']') else {
+                    return Err(format!("unterminated section header at line {line_number}"));
+                };
+                section = self.section_name(header[..end].trim());
+                sections
+                    .entry(section.clone())
+                    .or_insert_with(|| Value::Object(Map::new()));
+                continue;
+            }
+            let Some(separator) = line.find(['=', ':']) else {
+                return Err(format!("expected `key = value` at line {line_number}"));
+            };
+            let key = line[..separator].trim().to_string();
+            let value = unquote(strip_inline_comment(line[separator + 1..].trim()));
+            if let Some(Value::Object(keys)) = sections.get_mut(&section) {
+                keys.insert(key, Value::String(value.to_string()));
+            }
+        }
+        Ok(Value::Object(sections))
+    }
+}
+
+tree_file_handler!(IniFileHandler, "setup.cfg", impl TreeFormat);
+
+impl TreeFormat for IniFileHandler {
+    /// Resolves a key within the parsed sections.
+    ///
+    /// # Returns
+    ///
+    /// If found, returns the value associated with the key together with the section-qualified
+    /// key, e.g. `"database.host"`.
+    fn lookup<'v>(
+        &self,
+        _tree: &TreeFile,
+        index: &'v TreeIndex,
+        key: &str,
+    ) -> Result<Option<(&'v Value, String)>, Error> {
+        let Some(sections) = index.json() else {
+            return Ok(None);
+        };
+        let lookup = |section: &str, name: &str| {
+            sections
+                .get(self.section_name(section))
+                .and_then(|keys| keys.get(name))
+                .map(|value| (value, key.to_string()))
+        };
+        Ok(lookup("", key).or_else(|| {
+            key.match_indices('.')
+                .find_map(|(i, _)| lookup(&key[..i], &key[i + 1..]))
+        }))
+    }
+
+    /// Returns all keys, qualified by their section.
+    fn key_names(&self, index: &TreeIndex) -> Vec<String> {
+        let Some(Value::Object(sections)) = index.json() else {
+            return Vec::new();
+        };
+        sections
+            .iter()
+            .filter_map(|(section, keys)| Some((section, keys.as_object()?)))
+            .flat_map(|(section, keys)| {
+                keys.keys().map(move |name| {
+                    if section.is_empty() {
+                        name.clone()
+                    } else {
+                        format!("{section}.{name}")
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+/// Removes an inline comment, which starts with whitespace followed by `;` or `#` outside of
+/// quotes.
+fn strip_inline_comment(value: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in value.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if (c == '"' || c == '\'') && previous.is_whitespace() => quote = Some(c),
+            None if (c == ';' || c == '#') && previous.is_whitespace() => {
+                return value[..i].trim_end();
+            }
+            None => {}
+        }
+        previous = c;
+    }
+    value
+}
+
+/// Removes a pair of double or single quotes enclosing the whole value.
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+impl<'a> From<IniFileHandler> for Box<dyn Handler + 'a> {
+    fn from(handler: IniFileHandler) -> Self {
+        Box::new(handler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    const CONFIG: &str = r#"
+; global settings
+name = example
+verbose: true
+
+[Database]
+host = localhost   ; the primary
+port=5432
+# the password contains a semicolon
+password = "p@ss ;word"
+greeting = 'hello world'
+
+[server.alpha]
+hosts = one, \
+        two
+"#;
+
+    fn ini_file(content: &str) -> NamedTempFile {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(temp_file, "{content}").unwrap();
+        temp_file
+    }
+
+    #[test]
+    fn test_retrieves_global_value() {
+        let temp_file = ini_file(CONFIG);
+        let handler = IniFileHandler::new(temp_file.path());
+        assert_eq!(handler.handle_request("name"), Some("example".to_string()));
+        assert_eq!(handler.handle_request("verbose"), Some("true".to_string()));
+    }
+
+    #[test]
+    fn test_retrieves_section_value() {
+        let temp_file = ini_file(CONFIG);
+        let handler = IniFileHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("database.port"),
+            Some("5432".to_string())
+        );
+        assert_eq!(handler.handle_request("port"), None);
+    }
+
+    #[test]
+    fn test_strips_inline_comments() {
+        let temp_file = ini_file(CONFIG);
+        let handler = IniFileHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("database.host"),
+            Some("localhost".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unquotes_values() {
+        let temp_file = ini_file(CONFIG);
+        let handler = IniFileHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("database.password"),
+            Some("p@ss ;word".to_string())
+        );
+        assert_eq!(
+            handler.handle_request("database.greeting"),
+            Some("hello world".to_string())
+        );
+    }
+
+    #[test]
+    fn test_joins_continuation_lines() {
+        let temp_file = ini_file(CONFIG);
+        let handler = IniFileHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("server.alpha.hosts"),
+            Some("one, two".to_string())
+        );
+    }
+
+    #[test]
+    fn test_does_not_continue_comment_lines() {
+        let temp_file = ini_file("; a comment ending in \\\nname = example\n");
+        let handler = IniFileHandler::new(temp_file.path());
+        assert_eq!(handler.handle_request("name"), Some("example".to_string()));
+    }
+
+    #[test]
+    fn test_matches_sections_case_sensitively() {
+        let temp_file = ini_file(CONFIG);
+        let handler = IniFileHandler::new(temp_file.path()).case_sensitive(true);
+        assert_eq!(handler.handle_request("database.port"), None);
+        assert_eq!(
+            handler.handle_request("Database.port"),
+            Some("5432".to_string())
+        );
+    }
+
+    #[test]
+    fn test_enumerates_qualified_keys() {
+        let temp_file = ini_file("name = example\n[db]\nhost = localhost\n");
+        let handler = IniFileHandler::new(temp_file.path());
+        let expected = vec!["name".to_string(), "db.host".to_string()];
+        assert_eq!(handler.keys(), Some(expected));
+    }
+
+    #[test]
+    fn test_reports_source() {
+        let temp_file = ini_file(CONFIG);
+        let handler = IniFileHandler::new(temp_file.path());
+        let expected = Source::FileKey {
+            format: "INI",
+            path: temp_file.path().to_path_buf(),
+            key: "database.port".to_string(),
+        };
+        assert_eq!(
+            handler.handle_request_with_source("database.port"),
+            Some(("5432".to_string(), expected))
+        );
+    }
+
+    #[test]
+    fn test_reports_parse_error_with_line() {
+        let temp_file = ini_file("[db]\nhost = localhost\n[broken\n");
+        let handler = IniFileHandler::new(temp_file.path());
+        match handler.try_handle_request("db.host") {
+            Err(Error::Parse { reason, .. }) => assert!(reason.contains("line 3"), "{reason}"),
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+}
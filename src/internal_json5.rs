@@ -0,0 +1,154 @@
+//! A handler for JSON5 files.
+//!
+//! See [`Json5FileHandler`] for details.
+
+use super::*;
+
+/// A handler for retrieving values from a specified JSON5 file.
+///
+/// JSON5 is a superset of JSON suited to hand-edited files. In addition to plain JSON, it
+/// accepts:
+///
+/// * `// line` and `/* block */` comments.
+/// * Trailing commas in objects and arrays.
+/// * Unquoted object keys, and single-quoted strings.
+/// * Hexadecimal numbers, and numbers with a leading or trailing decimal point or a leading `+`.
+///
+/// This makes it suitable for JSONC files such as those used by editors, as well. Keys are
+/// resolved and values are returned as described for [`JSONFileHandler`].
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{Handler, Json5FileHandler};
+///
+/// // Create a new Json5FileHandler specifying a path to a file.
+/// let handler = Json5FileHandler::new("settings.json5");
+///
+/// // Handle a configuration request matching a `"some_key"` within `settings.json5`
+/// let value = handler.handle_request("some_key");
+/// ```
+pub struct Json5FileHandler {
+    /// The JSON5 file, together with the cached index of its keys.
+    tree: TreeFile,
+}
+
+impl Json5FileHandler {
+    /// Creates a new `Json5FileHandler` with the specified file path.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - The path to the JSON5 file from which values are to be retrieved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::Json5FileHandler;
+    ///
+    /// let handler = Json5FileHandler::new("settings.json5");
+    /// ```
+    #[allow(dead_code)]
+    pub fn new<P>(file_path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Json5FileHandler {
+            tree: TreeFile::new(file_path, "JSON5"),
+        }
+    }
+
+    /// Parses the content of the file as JSON5.
+    fn parse_content(&self, content: &str) -> Result<Value, String> {
+        json5::from_str(content).map_err(|e| e.to_string())
+    }
+}
+
+tree_file_handler!(Json5FileHandler, "settings.json5");
+
+impl<'a> From<Json5FileHandler> for Box<dyn Handler + 'a> {
+    fn from(handler: Json5FileHandler) -> Self {
+        Box::new(handler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    const CONFIG: &str = r#"
+// Settings edited by hand.
+{
+    name: 'example',
+    /* the server */
+    server: {
+        "host": "localhost",
+        port: 0x1F90,
+        ratio: .5,
+    },
+    tags: ['a', 'b',],
+}
+"#;
+
+    fn json5_file() -> NamedTempFile {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(temp_file, "{CONFIG}").unwrap();
+        temp_file
+    }
+
+    #[test]
+    fn test_accepts_comments_and_unquoted_keys() {
+        let temp_file = json5_file();
+        let handler = Json5FileHandler::new(temp_file.path());
+        assert_eq!(handler.handle_request("name"), Some("example".to_string()));
+        assert_eq!(
+            handler.handle_request("server.host"),
+            Some("localhost".to_string())
+        );
+    }
+
+    #[test]
+    fn test_accepts_json5_numbers() {
+        let temp_file = json5_file();
+        let handler = Json5FileHandler::new(temp_file.path());
+        assert_eq!(handler.handle_request("port"), Some("8080".to_string()));
+        assert_eq!(handler.handle_request("ratio"), Some("0.5".to_string()));
+    }
+
+    #[test]
+    fn test_accepts_trailing_commas() {
+        let temp_file = json5_file();
+        let handler = Json5FileHandler::new(temp_file.path());
+        let expected = vec!["a".to_string(), "b".to_string()];
+        assert_eq!(handler.handle_request_all("tags"), Some(expected));
+    }
+
+    #[test]
+    fn test_reports_source() {
+        let temp_file = json5_file();
+        let handler = Json5FileHandler::new(temp_file.path());
+        let expected = Source::FileKey {
+            format: "JSON5",
+            path: temp_file.path().to_path_buf(),
+            key: "server.host".to_string(),
+        };
+        assert_eq!(
+            handler.handle_request_with_source("host"),
+            Some(("localhost".to_string(), expected))
+        );
+    }
+
+    #[test]
+    fn test_reports_parse_error_with_location() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "{{\n  name: 'example',\n  port: ,\n}}").unwrap();
+
+        let handler = Json5FileHandler::new(temp_file.path());
+        match handler.try_handle_request("name") {
+            Err(Error::Parse { reason, .. }) => assert!(reason.contains("3:9"), "{reason}"),
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,844 @@
+//! Asynchronous handlers for use within a tokio runtime.
+//!
+//! See [`AsyncHandler`] for details.
+
+use std::future::{self, Future};
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::task::{self, JoinError};
+
+use super::*;
+
+/// A boxed future returned by the methods of an [`AsyncHandler`].
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// An asynchronous version of the [`Handler`] trait.
+///
+/// Every [`Handler`] is also an `AsyncHandler` which answers immediately, so handlers which do
+/// not block, such as the [`EnvHandler`], can be mixed with asynchronous sources in an
+/// [`AsyncChain`]. Handlers which block, such as the [`FileHandler`], should be wrapped in a
+/// [`BlockingHandler`] instead, e.g. by using an [`AsyncFileHandler`].
+///
+/// The methods carry an `_async` suffix so they do not clash with the methods of [`Handler`],
+/// which every sync handler implements as well.
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{AsyncChain, AsyncHandler, BoxFuture, DefaultHandler};
+///
+/// /// A handler which would look values up in a remote key-value store.
+/// struct RemoteHandler;
+///
+/// impl AsyncHandler for RemoteHandler {
+///     fn handle_request_async<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<String>> {
+///         Box::pin(async move {
+///             // e.g. `client.get(key).await`
+///             (key == "region").then(|| "eu-west-1".to_string())
+///         })
+///     }
+/// }
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let handler = AsyncChain::new()
+///     .with(RemoteHandler)
+///     .with(DefaultHandler::new("some_value"));
+///
+/// let value = handler.handle_request_async("region").await;
+/// assert_eq!(value, Some("eu-west-1".to_string()));
+/// # });
+/// ```
+pub trait AsyncHandler: Send + Sync {
+    /// Handles a request based on the provided key.
+    ///
+    /// See [`Handler::handle_request`].
+    fn handle_request_async<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<String>>;
+
+    /// Handles a request based on the provided key, reporting failures of the underlying source.
+    ///
+    /// The default implementation delegates to [`AsyncHandler::handle_request_async`] and never
+    /// fails. See [`Handler::try_handle_request`].
+    fn try_handle_request_async<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, Error>> {
+        Box::pin(async move { Ok(self.handle_request_async(key).await) })
+    }
+
+    /// Handles a request based on the provided key, reporting where the value came from.
+    ///
+    /// The default implementation delegates to [`AsyncHandler::handle_request_async`] and
+    /// reports [`Source::Unknown`]. See [`Handler::handle_request_with_source`].
+    fn handle_request_with_source_async<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Option<(String, Source)>> {
+        Box::pin(async move {
+            self.handle_request_async(key)
+                .await
+                .map(|value| (value, Source::Unknown))
+        })
+    }
+
+    /// Handles a request based on the provided key, reporting where the value came from and
+    /// failures of the underlying source.
+    ///
+    /// The default implementation delegates to [`AsyncHandler::handle_request_with_source_async`]
+    /// and never fails. See [`Handler::try_handle_request_with_source`].
+    fn try_handle_request_with_source_async<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<(String, Source)>, Error>> {
+        Box::pin(async move { Ok(self.handle_request_with_source_async(key).await) })
+    }
+
+    /// Handles a request for a key which may be associated with multiple values.
+    ///
+    /// The default implementation delegates to [`AsyncHandler::handle_request_async`] and
+    /// returns its value as the only element. See [`Handler::handle_request_all`].
+    fn handle_request_all_async<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Vec<String>>> {
+        Box::pin(async move {
+            self.handle_request_async(key)
+                .await
+                .map(|value| vec![value])
+        })
+    }
+
+    /// Handles a request for a key which may be associated with multiple values, reporting
+    /// failures of the underlying source.
+    ///
+    /// The default implementation delegates to [`AsyncHandler::handle_request_all_async`] and
+    /// never fails. See [`Handler::try_handle_request_all`].
+    fn try_handle_request_all_async<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Vec<String>>, Error>> {
+        Box::pin(async move { Ok(self.handle_request_all_async(key).await) })
+    }
+
+    /// Enumerates the keys this handler can answer.
+    ///
+    /// The default implementation returns `None`. See [`Handler::keys`].
+    fn keys_async(&self) -> BoxFuture<'_, Option<Vec<String>>> {
+        Box::pin(future::ready(None))
+    }
+
+    /// Returns how an [`AsyncChain`] treats a failure of this handler's source.
+    ///
+    /// The default implementation returns [`ErrorPolicy::Fail`]. See [`Handler::error_policy`].
+    fn error_policy_async(&self) -> ErrorPolicy {
+        ErrorPolicy::default()
+    }
+}
+
+impl<H: Handler + Send + Sync + ?Sized> AsyncHandler for H {
+    fn handle_request_async<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(future::ready(self.handle_request(key)))
+    }
+
+    fn try_handle_request_async<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, Error>> {
+        Box::pin(future::ready(self.try_handle_request(key)))
+    }
+
+    fn handle_request_with_source_async<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Option<(String, Source)>> {
+        Box::pin(future::ready(self.handle_request_with_source(key)))
+    }
+
+    fn try_handle_request_with_source_async<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<(String, Source)>, Error>> {
+        Box::pin(future::ready(self.try_handle_request_with_source(key)))
+    }
+
+    fn handle_request_all_async<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Vec<String>>> {
+        Box::pin(future::ready(self.handle_request_all(key)))
+    }
+
+    fn try_handle_request_all_async<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Vec<String>>, Error>> {
+        Box::pin(future::ready(self.try_handle_request_all(key)))
+    }
+
+    fn keys_async(&self) -> BoxFuture<'_, Option<Vec<String>>> {
+        Box::pin(future::ready(self.keys()))
+    }
+
+    fn error_policy_async(&self) -> ErrorPolicy {
+        self.error_policy()
+    }
+}
+
+/// An ordered list of asynchronous handlers which are queried in turn.
+///
+/// This is the asynchronous counterpart of a [`Chain`]. Since every [`Handler`] is also an
+/// [`AsyncHandler`], sync and async handlers can be mixed freely.
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{AsyncChain, AsyncHandler, AsyncJSONFileHandler, DefaultHandler, EnvHandler};
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let handler = AsyncChain::new()
+///     .with(EnvHandler::new().prefix("MYAPP_"))
+///     .with(AsyncJSONFileHandler::new("file.json"))
+///     .with(DefaultHandler::new("some_value"));
+///
+/// let value = handler.handle_request_async("some_key").await;
+/// # });
+/// ```
+#[derive(Default)]
+pub struct AsyncChain<'a> {
+    /// The handlers in the order in which they are queried.
+    handlers: Vec<Box<dyn AsyncHandler + 'a>>,
+}
+
+impl<'a> AsyncChain<'a> {
+    /// Creates a new, empty `AsyncChain`.
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        AsyncChain {
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Appends a handler to the end of the chain, returning the chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - The handler to query after all handlers already in the chain.
+    #[allow(dead_code)]
+    pub fn with<H: AsyncHandler + 'a>(mut self, handler: H) -> Self {
+        self.push(handler);
+        self
+    }
+
+    /// Appends a handler to the end of the chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - The handler to query after all handlers already in the chain.
+    #[allow(dead_code)]
+    pub fn push<H: AsyncHandler + 'a>(&mut self, handler: H) {
+        self.handlers.push(Box::new(handler));
+    }
+
+    /// Inserts a handler so that it is queried before the handler currently at `index`.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The position of the handler which should be queried after `handler`.
+    /// * `handler` - The handler to insert.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of handlers in the chain.
+    #[allow(dead_code)]
+    pub fn insert_before<H: AsyncHandler + 'a>(&mut self, index: usize, handler: H) {
+        self.handlers.insert(index, Box::new(handler));
+    }
+
+    /// Removes and returns the handler at `index`.
+    ///
+    /// # Returns
+    ///
+    /// The removed handler, or `None` if `index` is out of bounds.
+    #[allow(dead_code)]
+    pub fn remove(&mut self, index: usize) -> Option<Box<dyn AsyncHandler + 'a>> {
+        if index < self.handlers.len() {
+            Some(self.handlers.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over the handlers in the order in which they are queried.
+    #[allow(dead_code)]
+    pub fn iter(&self) -> impl Iterator<Item = &(dyn AsyncHandler + 'a)> {
+        self.handlers.iter().map(|handler| handler.as_ref())
+    }
+
+    /// Returns the number of handlers in the chain.
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.handlers.len()
+    }
+
+    /// Returns `true` if the chain contains no handlers.
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// Queries the handlers in turn with `query` until one has a value, passing failures on to
+    /// the next handler as described for [`ErrorPolicy`].
+    async fn find<'h, T, F>(&'h self, key: &str, query: F) -> Option<T>
+    where
+        F: Fn(&'h (dyn AsyncHandler + 'a)) -> BoxFuture<'h, Result<Option<T>, Error>>,
+    {
+        for handler in self.iter() {
+            let result = query(handler).await;
+            if let Some(found) = handler.error_policy_async().apply_infallible(key, result) {
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    /// Queries the handlers in turn with `query` until one has a value, applying the
+    /// [`ErrorPolicy`] of each handler to its failures.
+    async fn try_find<'h, T, F>(&'h self, key: &str, query: F) -> Result<Option<T>, Error>
+    where
+        F: Fn(&'h (dyn AsyncHandler + 'a)) -> BoxFuture<'h, Result<Option<T>, Error>>,
+    {
+        for handler in self.iter() {
+            let result = query(handler).await;
+            if let Some(found) = handler.error_policy_async().apply(key, result)? {
+                return Ok(Some(found));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'c> AsyncHandler for AsyncChain<'c> {
+    /// Returns the value of the first handler which has a value for the key.
+    ///
+    /// A handler which fails is skipped, as described for [`ErrorPolicy`].
+    fn handle_request_async<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(self.find(key, move |handler| handler.try_handle_request_async(key)))
+    }
+
+    /// Returns the value of the first handler which has a value for the key.
+    ///
+    /// If a handler fails before a value is found, its [`ErrorPolicy`] decides whether its
+    /// error is returned or the request is passed on to the remaining handlers.
+    fn try_handle_request_async<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, Error>> {
+        Box::pin(self.try_find(key, move |handler| handler.try_handle_request_async(key)))
+    }
+
+    /// Returns the value and [`Source`] of the first handler which has a value for the key.
+    ///
+    /// Failing handlers are treated as described for [`AsyncChain::handle_request_async`].
+    fn handle_request_with_source_async<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Option<(String, Source)>> {
+        Box::pin(self.find(key, move |handler| {
+            handler.try_handle_request_with_source_async(key)
+        }))
+    }
+
+    /// Returns the value and [`Source`] of the first handler which has a value for the key.
+    ///
+    /// Failing handlers are treated as described for [`AsyncChain::try_handle_request_async`].
+    fn try_handle_request_with_source_async<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<(String, Source)>, Error>> {
+        Box::pin(self.try_find(key, move |handler| {
+            handler.try_handle_request_with_source_async(key)
+        }))
+    }
+
+    /// Returns all values of the first handler which has a value for the key.
+    ///
+    /// Failing handlers are treated as described for [`AsyncChain::handle_request_async`].
+    fn handle_request_all_async<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Vec<String>>> {
+        Box::pin(self.find(key, move |handler| {
+            handler.try_handle_request_all_async(key)
+        }))
+    }
+
+    /// Returns all values of the first handler which has a value for the key.
+    ///
+    /// Failing handlers are treated as described for [`AsyncChain::try_handle_request_async`].
+    fn try_handle_request_all_async<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Vec<String>>, Error>> {
+        Box::pin(self.try_find(key, move |handler| {
+            handler.try_handle_request_all_async(key)
+        }))
+    }
+
+    /// Returns the union of the keys of all handlers which can enumerate their keys.
+    fn keys_async(&self) -> BoxFuture<'_, Option<Vec<String>>> {
+        Box::pin(async move {
+            let mut keys = None;
+            for handler in self.handlers.iter() {
+                keys = merge_keys(keys, handler.keys_async().await);
+            }
+            keys
+        })
+    }
+}
+
+impl<'a> FromIterator<Box<dyn AsyncHandler + 'a>> for AsyncChain<'a> {
+    fn from_iter<I: IntoIterator<Item = Box<dyn AsyncHandler + 'a>>>(iter: I) -> Self {
+        AsyncChain {
+            handlers: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// Runs a blocking [`Handler`] on tokio's blocking thread pool.
+///
+/// Handlers which perform I/O, such as the [`FileHandler`] and the [`JSONFileHandler`], block
+/// the thread which queries them. Wrapping them in a `BlockingHandler` moves each request onto a
+/// thread where blocking is acceptable, so the async executor is not stalled.
+///
+/// Requests must be made from within a tokio runtime.
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{AsyncHandler, BlockingHandler, FileHandler};
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let handler = BlockingHandler::new(FileHandler::new("/path/to/file"));
+/// let value = handler.handle_request_async("some_key").await;
+/// # });
+/// ```
+pub struct BlockingHandler<H> {
+    handler: Arc<H>,
+}
+
+impl<H: Handler + Send + Sync + 'static> BlockingHandler<H> {
+    /// Creates a new `BlockingHandler` wrapping the specified handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - The blocking handler to query on the blocking thread pool.
+    #[allow(dead_code)]
+    pub fn new(handler: H) -> Self {
+        BlockingHandler {
+            handler: Arc::new(handler),
+        }
+    }
+
+    /// Calls `f` with the wrapped handler and `key` on the blocking thread pool.
+    ///
+    /// # Returns
+    ///
+    /// The result of `f`, or an [`Error::Message`] if the task was cancelled because the runtime
+    /// is shutting down. If `f` panics, the panic is resumed on the calling task.
+    async fn spawn<T, F>(&self, key: &str, f: F) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(&H, &str) -> T + Send + 'static,
+    {
+        let handler = Arc::clone(&self.handler);
+        let key = key.to_string();
+        task::spawn_blocking(move || f(&handler, &key))
+            .await
+            .map_err(Self::join_error)
+    }
+
+    fn join_error(e: JoinError) -> Error {
+        match e.try_into_panic() {
+            Ok(panic) => std::panic::resume_unwind(panic),
+            Err(e) => Error::Message(format!("blocking handler task failed: {e}")),
+        }
+    }
+}
+
+impl<H: Handler + Send + Sync + 'static> AsyncHandler for BlockingHandler<H> {
+    fn handle_request_async<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move {
+            self.spawn(key, |handler, key| handler.handle_request(key))
+                .await
+                .ok()
+                .flatten()
+        })
+    }
+
+    fn try_handle_request_async<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, Error>> {
+        Box::pin(async move {
+            self.spawn(key, |handler, key| handler.try_handle_request(key))
+                .await?
+        })
+    }
+
+    fn handle_request_with_source_async<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Option<(String, Source)>> {
+        Box::pin(async move {
+            self.spawn(key, |handler, key| handler.handle_request_with_source(key))
+                .await
+                .ok()
+                .flatten()
+        })
+    }
+
+    fn try_handle_request_with_source_async<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<(String, Source)>, Error>> {
+        Box::pin(async move {
+            self.spawn(key, |handler, key| {
+                handler.try_handle_request_with_source(key)
+            })
+            .await?
+        })
+    }
+
+    fn handle_request_all_async<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Vec<String>>> {
+        Box::pin(async move {
+            self.spawn(key, |handler, key| handler.handle_request_all(key))
+                .await
+                .ok()
+                .flatten()
+        })
+    }
+
+    fn try_handle_request_all_async<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Vec<String>>, Error>> {
+        Box::pin(async move {
+            self.spawn(key, |handler, key| handler.try_handle_request_all(key))
+                .await?
+        })
+    }
+
+    fn keys_async(&self) -> BoxFuture<'_, Option<Vec<String>>> {
+        Box::pin(async move {
+            self.spawn("", |handler, _| handler.keys())
+                .await
+                .ok()
+                .flatten()
+        })
+    }
+
+    fn error_policy_async(&self) -> ErrorPolicy {
+        self.handler.error_policy()
+    }
+}
+
+/// Defines an asynchronous version of a file-based handler which queries it on tokio's blocking
+/// thread pool.
+macro_rules! async_file_handler {
+    ($(#[$meta:meta])* $name:ident, $handler:ident) => {
+        $(#[$meta])*
+        pub struct $name {
+            handler: BlockingHandler<$handler>,
+        }
+
+        impl $name {
+            #[doc = concat!("Creates a new `", stringify!($name), "` with the specified file path.")]
+            ///
+            /// # Arguments
+            ///
+            /// * `file_path` - The path to the file from which values are to be retrieved.
+            #[allow(dead_code)]
+            pub fn new<P>(file_path: P) -> Self
+            where
+                P: Into<PathBuf>,
+            {
+                $name {
+                    handler: BlockingHandler::new($handler::new(file_path)),
+                }
+            }
+        }
+
+        impl From<$handler> for $name {
+            fn from(handler: $handler) -> Self {
+                $name {
+                    handler: BlockingHandler::new(handler),
+                }
+            }
+        }
+
+        impl AsyncHandler for $name {
+            fn handle_request_async<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<String>> {
+                self.handler.handle_request_async(key)
+            }
+
+            fn try_handle_request_async<'a>(
+                &'a self,
+                key: &'a str,
+            ) -> BoxFuture<'a, Result<Option<String>, Error>> {
+                self.handler.try_handle_request_async(key)
+            }
+
+            fn handle_request_with_source_async<'a>(
+                &'a self,
+                key: &'a str,
+            ) -> BoxFuture<'a, Option<(String, Source)>> {
+                self.handler.handle_request_with_source_async(key)
+            }
+
+            fn try_handle_request_with_source_async<'a>(
+                &'a self,
+                key: &'a str,
+            ) -> BoxFuture<'a, Result<Option<(String, Source)>, Error>> {
+                self.handler.try_handle_request_with_source_async(key)
+            }
+
+            fn handle_request_all_async<'a>(
+                &'a self,
+                key: &'a str,
+            ) -> BoxFuture<'a, Option<Vec<String>>> {
+                self.handler.handle_request_all_async(key)
+            }
+
+            fn try_handle_request_all_async<'a>(
+                &'a self,
+                key: &'a str,
+            ) -> BoxFuture<'a, Result<Option<Vec<String>>, Error>> {
+                self.handler.try_handle_request_all_async(key)
+            }
+
+            fn keys_async(&self) -> BoxFuture<'_, Option<Vec<String>>> {
+                self.handler.keys_async()
+            }
+
+            fn error_policy_async(&self) -> ErrorPolicy {
+                self.handler.error_policy_async()
+            }
+        }
+    };
+}
+
+async_file_handler!(
+    /// An asynchronous [`FileHandler`], which reads the file on tokio's blocking thread pool.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::{AsyncFileHandler, AsyncHandler};
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let handler = AsyncFileHandler::new("/path/to/file");
+    /// let value = handler.handle_request_async("some_key").await;
+    /// # });
+    /// ```
+    AsyncFileHandler,
+    FileHandler
+);
+
+async_file_handler!(
+    /// An asynchronous [`JSONFileHandler`], which reads and parses the file on tokio's blocking
+    /// thread pool.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::{AsyncHandler, AsyncJSONFileHandler};
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let handler = AsyncJSONFileHandler::new("file.json");
+    /// let value = handler.handle_request_async("some_key").await;
+    /// # });
+    /// ```
+    ///
+    /// A configured [`JSONFileHandler`] can be converted into an `AsyncJSONFileHandler`:
+    ///
+    /// ```
+    /// use cor_args::{AsyncJSONFileHandler, ErrorPolicy, JSONFileHandler};
+    ///
+    /// let handler = JSONFileHandler::new("file.json").on_error(ErrorPolicy::Warn);
+    /// let handler = AsyncJSONFileHandler::from(handler);
+    /// ```
+    AsyncJSONFileHandler,
+    JSONFileHandler
+);
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    /// A handler which answers a single key asynchronously.
+    struct RemoteHandler;
+
+    impl AsyncHandler for RemoteHandler {
+        fn handle_request_async<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<String>> {
+            Box::pin(async move {
+                task::yield_now().await;
+                (key == "region").then(|| "eu-west-1".to_string())
+            })
+        }
+    }
+
+    mod async_chain {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_mixes_sync_and_async_handlers() {
+            env::set_var("ASYNC_CHAIN_TEST_verbosity", "debug");
+            let handler = AsyncChain::new()
+                .with(EnvHandler::new().prefix("ASYNC_CHAIN_TEST_"))
+                .with(RemoteHandler)
+                .with(DefaultHandler::new("DEFAULT_VALUE"));
+
+            let actual = handler.handle_request_async("verbosity").await;
+            assert_eq!(actual, Some("debug".to_string()));
+            let actual = handler.handle_request_async("region").await;
+            assert_eq!(actual, Some("eu-west-1".to_string()));
+            let actual = handler.handle_request_async("other").await;
+            assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
+        }
+
+        #[tokio::test]
+        async fn test_reports_source_of_async_handler() {
+            let handler = AsyncChain::new()
+                .with(RemoteHandler)
+                .with(DefaultHandler::new("DEFAULT_VALUE"));
+            let actual = handler.handle_request_with_source_async("region").await;
+            assert_eq!(actual, Some(("eu-west-1".to_string(), Source::Unknown)));
+            let actual = handler.handle_request_with_source_async("other").await;
+            assert_eq!(actual, Some(("DEFAULT_VALUE".to_string(), Source::Default)));
+        }
+
+        #[tokio::test]
+        async fn test_try_handle_request_stops_at_error() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, "not json").unwrap();
+            let handler = AsyncChain::new()
+                .with(AsyncJSONFileHandler::new(temp_file.path()))
+                .with(DefaultHandler::new("DEFAULT_VALUE"));
+
+            let actual = handler.try_handle_request_async("test_key").await;
+            assert!(matches!(actual, Err(Error::Parse { .. })));
+        }
+
+        #[tokio::test]
+        async fn test_try_with_source_and_all_stop_at_error() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, "not json").unwrap();
+            let handler = AsyncChain::new()
+                .with(AsyncJSONFileHandler::new(temp_file.path()))
+                .with(DefaultHandler::new("DEFAULT_VALUE"));
+
+            let actual = handler
+                .try_handle_request_with_source_async("test_key")
+                .await;
+            assert!(matches!(actual, Err(Error::Parse { .. })));
+            let actual = handler.try_handle_request_all_async("test_key").await;
+            assert!(matches!(actual, Err(Error::Parse { .. })));
+        }
+
+        #[tokio::test]
+        async fn test_handle_request_skips_error() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, "not json").unwrap();
+            let handler = AsyncChain::new()
+                .with(AsyncJSONFileHandler::new(temp_file.path()))
+                .with(DefaultHandler::new("DEFAULT_VALUE"));
+
+            let actual = handler.handle_request_async("test_key").await;
+            assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
+            let actual = handler.handle_request_with_source_async("test_key").await;
+            assert_eq!(actual, Some(("DEFAULT_VALUE".to_string(), Source::Default)));
+            let actual = handler.handle_request_all_async("test_key").await;
+            assert_eq!(actual, Some(vec!["DEFAULT_VALUE".to_string()]));
+        }
+
+        #[tokio::test]
+        async fn test_queries_each_handler_once() {
+            /// A handler counting how often it is queried.
+            struct CountingHandler(Arc<std::sync::atomic::AtomicUsize>);
+
+            impl AsyncHandler for CountingHandler {
+                fn handle_request_async<'a>(
+                    &'a self,
+                    _key: &'a str,
+                ) -> BoxFuture<'a, Option<String>> {
+                    self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    Box::pin(future::ready(Some("COUNTED".to_string())))
+                }
+            }
+
+            let count = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            let handler = AsyncChain::new().with(CountingHandler(Arc::clone(&count)));
+            handler.handle_request_with_source_async("").await;
+            handler.handle_request_all_async("").await;
+            assert_eq!(count.load(std::sync::atomic::Ordering::SeqCst), 2);
+        }
+
+        #[tokio::test]
+        async fn test_falls_through_error_by_policy() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, "not json").unwrap();
+            let json_handler =
+                JSONFileHandler::new(temp_file.path()).on_error(ErrorPolicy::FallThrough);
+            let handler = AsyncChain::new()
+                .with(AsyncJSONFileHandler::from(json_handler))
+                .with(DefaultHandler::new("DEFAULT_VALUE"));
+
+            let actual = handler.try_handle_request_async("test_key").await.unwrap();
+            assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
+        }
+
+        #[tokio::test]
+        async fn test_reorders_handlers() {
+            let mut handler = AsyncChain::new()
+                .with(RemoteHandler)
+                .with(DefaultHandler::new("DEFAULT_VALUE"));
+            let removed = handler.remove(1);
+            assert!(removed.is_some());
+            handler.insert_before(0, DefaultHandler::new("FIRST_VALUE"));
+
+            assert_eq!(handler.len(), 2);
+            let actual = handler.handle_request_async("region").await;
+            assert_eq!(actual, Some("FIRST_VALUE".to_string()));
+        }
+    }
+
+    mod async_file_handler {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_retrieves_file_content() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, "test_content").unwrap();
+            let handler = AsyncFileHandler::new(temp_file.path());
+
+            let actual = handler.handle_request_async("").await;
+            assert_eq!(actual, Some("test_content\n".to_string()));
+        }
+
+        #[tokio::test]
+        async fn test_returns_none_for_missing_file() {
+            let handler = AsyncFileHandler::new("/nonexistent/path/to/file");
+            let actual = handler.try_handle_request_async("").await.unwrap();
+            assert_eq!(actual, None);
+        }
+    }
+
+    mod async_json_file_handler {
+        use super::*;
+
+        #[tokio::test]
+        async fn test_retrieves_set_value() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, r#"{{"test_key": [1, 2]}}"#).unwrap();
+            let handler = AsyncJSONFileHandler::new(temp_file.path());
+
+            let actual = handler.handle_request_all_async("test_key").await;
+            assert_eq!(actual, Some(vec!["1".to_string(), "2".to_string()]));
+            let actual = handler.keys_async().await;
+            assert_eq!(actual, Some(vec!["test_key".to_string()]));
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! A handler for TOML files.
+//!
+//! See [`TomlFileHandler`] for details.
+
+use super::*;
+
+/// A handler for retrieving values from a specified TOML file.
+///
+/// Keys are resolved as described for [`JSONFileHandler`], so a key may be a bare name searched
+/// for at any depth, a dotted path into tables such as `database.host`, or a JSON Pointer such
+/// as `/database/host`. Arrays of tables are addressed by their index, e.g. `servers.0.port`.
+///
+/// Values are returned the same way regardless of their type in the file:
+///
+/// * Strings are returned without quotes.
+/// * Integers, floats and booleans are returned in their normalized form rather than as
+///   written, e.g. `0x1F` is returned as `31`, `1_000` as `1000` and `1e3` as `1000.0`. The
+///   special floats are returned as `inf`, `-inf` and `nan`.
+/// * Offset datetimes, local datetimes, local dates and local times are returned in their RFC
+///   3339 representation, e.g. `1979-05-27T07:32:00Z`.
+/// * Arrays and tables are returned as their JSON representation.
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{Handler, TomlFileHandler};
+///
+/// // Create a new TomlFileHandler specifying a path to a file.
+/// let handler = TomlFileHandler::new("Config.toml");
+///
+/// // Handle a configuration request matching a `"port"` within the `[server]` table.
+/// let value = handler.handle_request("server.port");
+/// ```
+pub struct TomlFileHandler {
+    /// The TOML file, together with the cached index of its keys.
+    tree: TreeFile,
+}
+
+impl TomlFileHandler {
+    /// Creates a new `TomlFileHandler` with the specified file path.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - The path to the TOML file from which values are to be retrieved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::TomlFileHandler;
+    ///
+    /// let handler = TomlFileHandler::new("Config.toml");
+    /// ```
+    #[allow(dead_code)]
+    pub fn new<P>(file_path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        TomlFileHandler {
+            tree: TreeFile::new(file_path, "TOML"),
+        }
+    }
+
+    /// Parses the content of the file as a TOML document.
+    fn parse_content(&self, content: &str) -> Result<Value, String> {
+        let table = content
+            .parse::<toml::Table>()
+            .map_err(|e| e.message().to_string() + &span_location(content, e.span()))?;
+        Ok(toml_to_json(toml::Value::Table(table)))
+    }
+}
+
+tree_file_handler!(TomlFileHandler, "Config.toml");
+
+impl<'a> From<TomlFileHandler> for Box<dyn Handler + 'a> {
+    fn from(handler: TomlFileHandler) -> Self {
+        Box::new(handler)
+    }
+}
+
+/// Describes where a parse error occurred, e.g. `" at line 3, column 7"`.
+fn span_location(content: &str, span: Option<std::ops::Range<usize>>) -> String {
+    let Some(span) = span else {
+        return String::new();
+    };
+    let before = &content[..span.start.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    format!(" at line {line}, column {column}")
+}
+
+/// Converts a TOML value into the equivalent JSON value.
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => match serde_json::Number::from_f64(f) {
+            Some(n) => Value::Number(n),
+            // JSON has no representation for the special floats, so keep TOML's spelling.
+            None if f.is_nan() => Value::String("nan".to_string()),
+            None if f > 0.0 => Value::String("inf".to_string()),
+            None => Value::String("-inf".to_string()),
+        },
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Array(arr) => Value::Array(arr.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    const CONFIG: &str = r#"
+title = "example"
+debug = true
+
+[database]
+host = "localhost"
+port = 5432
+ratio = 0.5
+created = 1979-05-27T07:32:00Z
+backup = 1979-05-27
+timeout = inf
+limit = 1e3
+mask = 0x1F
+
+[[servers]]
+name = "alpha"
+port = 8080
+
+[[servers]]
+name = "beta"
+port = 8081
+"#;
+
+    fn config_file() -> NamedTempFile {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(temp_file, "{CONFIG}").unwrap();
+        temp_file
+    }
+
+    #[test]
+    fn test_retrieves_root_value() {
+        let temp_file = config_file();
+        let handler = TomlFileHandler::new(temp_file.path());
+        assert_eq!(handler.handle_request("title"), Some("example".to_string()));
+    }
+
+    #[test]
+    fn test_retrieves_nested_value_by_name() {
+        let temp_file = config_file();
+        let handler = TomlFileHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("host"),
+            Some("localhost".to_string())
+        );
+    }
+
+    #[test]
+    fn test_retrieves_value_by_dotted_path() {
+        let temp_file = config_file();
+        let handler = TomlFileHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("database.port"),
+            Some("5432".to_string())
+        );
+    }
+
+    #[test]
+    fn test_retrieves_value_in_array_of_tables() {
+        let temp_file = config_file();
+        let handler = TomlFileHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("servers.1.port"),
+            Some("8081".to_string())
+        );
+        assert_eq!(
+            handler.handle_request("/servers/0/name"),
+            Some("alpha".to_string())
+        );
+    }
+
+    #[test]
+    fn test_stringifies_scalars() {
+        let temp_file = config_file();
+        let handler = TomlFileHandler::new(temp_file.path());
+        assert_eq!(handler.handle_request("debug"), Some("true".to_string()));
+        assert_eq!(handler.handle_request("ratio"), Some("0.5".to_string()));
+        assert_eq!(handler.handle_request("timeout"), Some("inf".to_string()));
+        assert_eq!(handler.handle_request("limit"), Some("1000.0".to_string()));
+        assert_eq!(handler.handle_request("mask"), Some("31".to_string()));
+    }
+
+    #[test]
+    fn test_stringifies_datetimes() {
+        let temp_file = config_file();
+        let handler = TomlFileHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("created"),
+            Some("1979-05-27T07:32:00Z".to_string())
+        );
+        assert_eq!(
+            handler.handle_request("backup"),
+            Some("1979-05-27".to_string())
+        );
+    }
+
+    #[test]
+    fn test_reports_source() {
+        let temp_file = config_file();
+        let handler = TomlFileHandler::new(temp_file.path());
+        let expected = Source::FileKey {
+            format: "TOML",
+            path: temp_file.path().to_path_buf(),
+            key: "database.host".to_string(),
+        };
+        assert_eq!(
+            handler.handle_request_with_source("host"),
+            Some(("localhost".to_string(), expected))
+        );
+    }
+
+    #[test]
+    fn test_reports_parse_error_with_location() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "title = \"example\"\nport = ").unwrap();
+
+        let handler = TomlFileHandler::new(temp_file.path());
+        match handler.try_handle_request("title") {
+            Err(Error::Parse { reason, .. }) => assert!(reason.contains("line 2"), "{reason}"),
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_returns_none_for_nonexistent_file() {
+        let handler = TomlFileHandler::new("");
+        assert_eq!(handler.try_handle_request("title").unwrap(), None);
+    }
+}
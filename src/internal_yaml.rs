@@ -0,0 +1,274 @@
+//! A handler for YAML files.
+//!
+//! See [`YamlFileHandler`] for details.
+
+use serde::Deserialize;
+
+use super::*;
+
+/// A handler for retrieving values from a specified YAML file.
+///
+/// Keys are resolved as described for [`JSONFileHandler`], so a key may be a bare name searched
+/// for at any depth, a dotted path such as `database.host` or `servers.0.port`, or a JSON
+/// Pointer such as `/database/host`.
+///
+/// The document is resolved before any key is looked up:
+///
+/// * Aliases are replaced by the value of their anchor, and `<<` merge keys are merged into the
+///   surrounding mapping.
+/// * Mapping keys which are not strings are converted to strings, e.g. `80`, `true` or `null`.
+/// * Tags are ignored, so `!secret abc` is returned as `abc`.
+///
+/// Files containing more than one document, separated by `---`, are read from the first
+/// document unless another one is [selected](YamlFileHandler::document).
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{Handler, YamlFileHandler};
+///
+/// // Create a new YamlFileHandler specifying a path to a file.
+/// let handler = YamlFileHandler::new("config.yaml");
+///
+/// // Handle a configuration request matching a `"port"` within the `server` mapping.
+/// let value = handler.handle_request("server.port");
+/// ```
+pub struct YamlFileHandler {
+    /// The YAML file, together with the cached index of its keys.
+    tree: TreeFile,
+    /// The zero-based index of the document values are retrieved from.
+    document: usize,
+}
+
+impl YamlFileHandler {
+    /// Creates a new `YamlFileHandler` with the specified file path.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - The path to the YAML file from which values are to be retrieved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::YamlFileHandler;
+    ///
+    /// let handler = YamlFileHandler::new("config.yaml");
+    /// ```
+    #[allow(dead_code)]
+    pub fn new<P>(file_path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        YamlFileHandler {
+            tree: TreeFile::new(file_path, "YAML"),
+            document: 0,
+        }
+    }
+
+    /// Selects the document values are retrieved from, in a file containing several documents.
+    ///
+    /// If the file contains fewer documents, no values are found.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The zero-based index of the document. Defaults to `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::YamlFileHandler;
+    ///
+    /// // Read the second document of the file.
+    /// let handler = YamlFileHandler::new("config.yaml").document(1);
+    /// ```
+    #[allow(dead_code)]
+    pub fn document(mut self, document: usize) -> Self {
+        self.document = document;
+        self
+    }
+
+    /// Parses the selected document of the file as YAML.
+    ///
+    /// The reason of a failure includes the line and column at which it occurred.
+    fn parse_content(&self, content: &str) -> Result<Value, String> {
+        let Some(document) = serde_yaml::Deserializer::from_str(content).nth(self.document) else {
+            return Ok(Value::Null);
+        };
+        let mut value = serde_yaml::Value::deserialize(document).map_err(|e| e.to_string())?;
+        value.apply_merge().map_err(|e| e.to_string())?;
+        Ok(yaml_to_json(value))
+    }
+}
+
+tree_file_handler!(YamlFileHandler, "config.yaml");
+
+impl<'a> From<YamlFileHandler> for Box<dyn Handler + 'a> {
+    fn from(handler: YamlFileHandler) -> Self {
+        Box::new(handler)
+    }
+}
+
+/// Converts a YAML value into the equivalent JSON value.
+fn yaml_to_json(value: serde_yaml::Value) -> Value {
+    match value {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(b) => Value::Bool(b),
+        serde_yaml::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Value::from(i)
+            } else if let Some(u) = n.as_u64() {
+                Value::from(u)
+            } else {
+                // JSON has no representation for the special floats, so keep YAML's spelling.
+                n.as_f64()
+                    .and_then(serde_json::Number::from_f64)
+                    .map_or_else(|| Value::String(n.to_string()), Value::Number)
+            }
+        }
+        serde_yaml::Value::String(s) => Value::String(s),
+        serde_yaml::Value::Sequence(seq) => {
+            Value::Array(seq.into_iter().map(yaml_to_json).collect())
+        }
+        serde_yaml::Value::Mapping(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (yaml_key_to_string(key), yaml_to_json(value)))
+                .collect(),
+        ),
+        serde_yaml::Value::Tagged(tagged) => yaml_to_json(tagged.value),
+    }
+}
+
+/// Converts a YAML mapping key into the name it is looked up by.
+fn yaml_key_to_string(key: serde_yaml::Value) -> String {
+    match yaml_to_json(key) {
+        Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    fn yaml_file(content: &str) -> NamedTempFile {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(temp_file, "{content}").unwrap();
+        temp_file
+    }
+
+    #[test]
+    fn test_retrieves_nested_value() {
+        let temp_file = yaml_file("server:\n  host: localhost\n  port: 8080\n");
+        let handler = YamlFileHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("host"),
+            Some("localhost".to_string())
+        );
+        assert_eq!(
+            handler.handle_request("server.port"),
+            Some("8080".to_string())
+        );
+    }
+
+    #[test]
+    fn test_retrieves_value_in_sequence() {
+        let temp_file = yaml_file("servers:\n  - name: alpha\n  - name: beta\n");
+        let handler = YamlFileHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("servers.1.name"),
+            Some("beta".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolves_aliases_and_merge_keys() {
+        let temp_file = yaml_file(
+            "defaults: &defaults\n  adapter: postgres\n  host: localhost\n\
+             development:\n  <<: *defaults\n  host: dev.example.com\n\
+             test:\n  database: *defaults\n",
+        );
+        let handler = YamlFileHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("development.adapter"),
+            Some("postgres".to_string())
+        );
+        assert_eq!(
+            handler.handle_request("development.host"),
+            Some("dev.example.com".to_string())
+        );
+        assert_eq!(
+            handler.handle_request("test.database.host"),
+            Some("localhost".to_string())
+        );
+    }
+
+    #[test]
+    fn test_selects_document() {
+        let temp_file = yaml_file("name: first\n---\nname: second\n");
+        let handler = YamlFileHandler::new(temp_file.path());
+        assert_eq!(handler.handle_request("name"), Some("first".to_string()));
+
+        let handler = YamlFileHandler::new(temp_file.path()).document(1);
+        assert_eq!(handler.handle_request("name"), Some("second".to_string()));
+
+        let handler = YamlFileHandler::new(temp_file.path()).document(2);
+        assert_eq!(handler.try_handle_request("name").unwrap(), None);
+    }
+
+    #[test]
+    fn test_converts_non_string_keys() {
+        let temp_file = yaml_file("ports:\n  80: http\n  443: https\nflags:\n  true: yes\n");
+        let handler = YamlFileHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("ports.443"),
+            Some("https".to_string())
+        );
+        assert_eq!(handler.handle_request("true"), Some("yes".to_string()));
+    }
+
+    #[test]
+    fn test_ignores_tags() {
+        let temp_file = yaml_file("password: !secret hunter2\n");
+        let handler = YamlFileHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("password"),
+            Some("hunter2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_reports_source() {
+        let temp_file = yaml_file("server:\n  host: localhost\n");
+        let handler = YamlFileHandler::new(temp_file.path());
+        let expected = Source::FileKey {
+            format: "YAML",
+            path: temp_file.path().to_path_buf(),
+            key: "server.host".to_string(),
+        };
+        assert_eq!(
+            handler.handle_request_with_source("host"),
+            Some(("localhost".to_string(), expected))
+        );
+    }
+
+    #[test]
+    fn test_reports_parse_error_with_location() {
+        let temp_file = yaml_file("server:\n  host: localhost\n port: 8080\n");
+        let handler = YamlFileHandler::new(temp_file.path());
+        match handler.try_handle_request("host") {
+            Err(Error::Parse { reason, .. }) => {
+                assert!(reason.contains("line 3 column 2"), "{reason}")
+            }
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_returns_none_for_nonexistent_file() {
+        let handler = YamlFileHandler::new("");
+        assert_eq!(handler.try_handle_request("host").unwrap(), None);
+    }
+}
@@ -0,0 +1,159 @@
+//! A handler which renames keys, used by `#[derive(CorArgs)]`.
+//!
+//! See [`KeyMapHandler`] for details.
+
+use super::*;
+
+/// A handler which answers requests by looking up a differently named key in another handler.
+///
+/// Each source usually spells a setting differently, e.g. `--port`, `MYAPP_PORT` and
+/// `server.port`. A `KeyMapHandler` translates the requested key into the key understood by the
+/// wrapped handler. Keys which have not been mapped are not forwarded, so a broad handler such as
+/// the [`DefaultHandler`] only answers the keys it was mapped to.
+///
+/// This is used by the code generated by `#[derive(CorArgs)]` and is not part of the public API.
+pub struct KeyMapHandler<H> {
+    handler: H,
+    /// Pairs of requested keys and the keys they are looked up as in `handler`.
+    keys: Vec<(String, String)>,
+}
+
+impl<H: Handler> KeyMapHandler<H> {
+    /// Creates a new `KeyMapHandler` wrapping the specified handler, without any mapped keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - The handler to look up mapped keys in.
+    #[allow(dead_code)]
+    pub fn new(handler: H) -> Self {
+        KeyMapHandler {
+            handler,
+            keys: Vec::new(),
+        }
+    }
+
+    /// Maps a requested key to the key it is looked up as in the wrapped handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key passed to `handle_request()`.
+    /// * `source_key` - The key passed on to the wrapped handler.
+    #[allow(dead_code)]
+    pub fn map<K, S>(mut self, key: K, source_key: S) -> Self
+    where
+        K: Into<String>,
+        S: Into<String>,
+    {
+        self.keys.push((key.into(), source_key.into()));
+        self
+    }
+
+    fn source_key(&self, key: &str) -> Option<&str> {
+        self.keys
+            .iter()
+            .find(|(mapped, _)| mapped == key)
+            .map(|(_, source_key)| source_key.as_str())
+    }
+}
+
+impl<H: Handler> Handler for KeyMapHandler<H> {
+    fn handle_request(&self, key: &str) -> Option<String> {
+        self.source_key(key)
+            .and_then(|source_key| self.handler.handle_request(source_key))
+    }
+
+    fn try_handle_request(&self, key: &str) -> Result<Option<String>, Error> {
+        match self.source_key(key) {
+            Some(source_key) => self.handler.try_handle_request(source_key),
+            None => Ok(None),
+        }
+    }
+
+    /// Reports the [`Source`] of the wrapped handler, which names the key it was looked up as.
+    fn handle_request_with_source(&self, key: &str) -> Option<(String, Source)> {
+        self.source_key(key)
+            .and_then(|source_key| self.handler.handle_request_with_source(source_key))
+    }
+
+    fn try_handle_request_with_source(&self, key: &str) -> Result<Option<(String, Source)>, Error> {
+        match self.source_key(key) {
+            Some(source_key) => self.handler.try_handle_request_with_source(source_key),
+            None => Ok(None),
+        }
+    }
+
+    fn handle_request_all(&self, key: &str) -> Option<Vec<String>> {
+        self.source_key(key)
+            .and_then(|source_key| self.handler.handle_request_all(source_key))
+    }
+
+    fn try_handle_request_all(&self, key: &str) -> Result<Option<Vec<String>>, Error> {
+        match self.source_key(key) {
+            Some(source_key) => self.handler.try_handle_request_all(source_key),
+            None => Ok(None),
+        }
+    }
+
+    /// Enumerates the mapped keys which the wrapped handler has a value for.
+    fn keys(&self) -> Option<Vec<String>> {
+        let keys = self
+            .keys
+            .iter()
+            .filter(|(_, source_key)| self.handler.handle_request(source_key).is_some())
+            .map(|(key, _)| key.clone())
+            .collect();
+        Some(keys)
+    }
+
+    fn error_policy(&self) -> ErrorPolicy {
+        self.handler.error_policy()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retrieves_mapped_key() {
+        env::set_var("KEY_MAP_TEST_PORT", "9090");
+        let handler = KeyMapHandler::new(EnvHandler::new()).map("port", "KEY_MAP_TEST_PORT");
+        let actual = handler.handle_request("port");
+        assert_eq!(actual, Some("9090".to_string()));
+    }
+
+    #[test]
+    fn test_returns_none_for_unmapped_key() {
+        let handler = KeyMapHandler::new(DefaultHandler::new("TEST_VAL")).map("port", "port");
+        let actual = handler.handle_request("host");
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn test_reports_source_of_wrapped_handler() {
+        env::set_var("KEY_MAP_SOURCE_TEST_PORT", "9090");
+        let handler = KeyMapHandler::new(EnvHandler::new()).map("port", "KEY_MAP_SOURCE_TEST_PORT");
+        let (_, source) = handler.handle_request_with_source("port").unwrap();
+        assert_eq!(
+            source,
+            Source::Env {
+                var: "KEY_MAP_SOURCE_TEST_PORT".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_enumerates_mapped_keys_with_values() {
+        env::set_var("KEY_MAP_KEYS_TEST_PORT", "9090");
+        let handler = KeyMapHandler::new(EnvHandler::new())
+            .map("port", "KEY_MAP_KEYS_TEST_PORT")
+            .map("host", "KEY_MAP_KEYS_TEST_HOST");
+        assert_eq!(handler.keys(), Some(vec!["port".to_string()]));
+    }
+
+    #[test]
+    fn test_forwards_policy() {
+        let handler = KeyMapHandler::new(FileHandler::new("").on_error(ErrorPolicy::Warn));
+        assert_eq!(handler.error_policy(), ErrorPolicy::Warn);
+    }
+}
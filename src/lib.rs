@@ -1,19 +1,76 @@
+use base64::prelude::{Engine, BASE64_STANDARD};
+use serde::de::DeserializeOwned;
 use serde_json::Value;
 use std::borrow::Cow;
 use std::env;
-use std::fs::File;
-use std::io::Read;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
 use std::path::PathBuf;
-
+use std::str::FromStr;
+use std::sync::Arc;
+
+use self::tree::{tree_file_handler, TreeFile, TreeFormat, TreeIndex};
+
+mod config_file;
+mod de;
+mod discovery;
+mod dotenv;
+mod drop_in;
+mod ini;
+#[cfg(feature = "json5")]
+pub mod internal_json5;
+#[cfg(feature = "tokio")]
+pub mod internal_tokio;
+#[cfg(feature = "toml")]
+pub mod internal_toml;
+#[cfg(feature = "yaml")]
+pub mod internal_yaml;
+#[cfg(feature = "derive")]
+mod key_map;
+mod properties;
+mod tree;
+
+pub use self::config_file::{ConfigFileHandler, FileFormat};
+pub use self::de::from_handler;
+pub use self::discovery::ConfigDiscovery;
+pub use self::dotenv::DotenvHandler;
+pub use self::drop_in::DropInDirHandler;
+pub use self::ini::IniFileHandler;
 #[cfg(feature = "clap")]
 pub use self::internal_clap::*;
 #[cfg(feature = "config")]
 pub use self::internal_config::*;
+#[cfg(feature = "json5")]
+pub use self::internal_json5::*;
+#[cfg(feature = "tokio")]
+pub use self::internal_tokio::*;
+#[cfg(feature = "toml")]
+pub use self::internal_toml::*;
+#[cfg(feature = "yaml")]
+pub use self::internal_yaml::*;
+pub use self::properties::PropertiesFileHandler;
+#[cfg(feature = "derive")]
+pub use cor_args_derive::CorArgs;
+
+// Lets the code generated by `#[derive(CorArgs)]` refer to `::cor_args` from within this crate.
+#[cfg(feature = "derive")]
+extern crate self as cor_args;
+
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod __private {
+    pub use super::key_map::KeyMapHandler;
+    pub use clap;
+}
 
 /// A trait for handling requests based on a key.
 ///
 /// This trait provides a mechanism for handling requests by taking a key and
 /// returning an associated value wrapped in an `Option`.
+///
+/// Handlers which are also `Send + Sync`, such as all handlers of this crate, can be shared
+/// across threads in a [`SharedChain`].
 pub trait Handler {
     /// Handles a request based on the provided key.
     ///
@@ -26,853 +83,3335 @@ pub trait Handler {
     /// An `Option` wrapping a `String` value associated with the key.
     /// If there's no value associated with the key, it should return `None`.
     fn handle_request(&self, key: &str) -> Option<String>;
-}
 
-/// A default implementation of the `Handler` trait.
-///
-/// This struct contains a single `value` that will be returned for any request,
-/// regardless of the provided key.
-///
-/// # Examples
-///
-/// ```
-/// use cor_args::{DefaultHandler, Handler};
-///
-/// // Create a new DefaultHandler for a specific value
-/// let handler = DefaultHandler::new("some_value");
-///
-/// // Add a fallback handler
-/// //let handler = handler.next(some_other_handler.into());
-///
-/// // Handle a configuration request
-/// let value = handler.handle_request("some_key");
-/// ```
-pub struct DefaultHandler {
-    value: String,
-}
+    /// Handles a request based on the provided key, reporting failures of the underlying source.
+    ///
+    /// Unlike [`Handler::handle_request`], which treats an unreadable or malformed source the same
+    /// as a missing key, this method distinguishes the two cases.
+    ///
+    /// The default implementation delegates to [`Handler::handle_request`] and never fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key associated with the request.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(Some(value))` if a value is associated with the key, `Ok(None)` if there's no value
+    /// associated with the key, or an [`Error`] if the underlying source could not be read.
+    fn try_handle_request(&self, key: &str) -> Result<Option<String>, Error> {
+        Ok(self.handle_request(key))
+    }
 
-impl DefaultHandler {
-    /// Creates a new `DefaultHandler` with the specified value.
+    /// Handles a request based on the provided key, reporting where the value came from.
+    ///
+    /// The default implementation delegates to [`Handler::handle_request`] and reports
+    /// [`Source::Unknown`].
     ///
     /// # Arguments
     ///
-    /// * `value` - The value to be returned for any request.
+    /// * `key` - The key associated with the request.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` wrapping the value associated with the key together with a [`Source`]
+    /// describing the handler which answered the request.
     ///
     /// # Examples
     ///
     /// ```
-    /// use cor_args::DefaultHandler;
+    /// use cor_args::{DefaultHandler, Handler, Source};
     ///
-    /// let handler = DefaultHandler::new("some_value");
+    /// let handler = DefaultHandler::new("trace");
+    /// let (value, source) = handler.handle_request_with_source("verbosity").unwrap();
+    /// assert_eq!(value, "trace");
+    /// assert_eq!(source, Source::Default);
     /// ```
-    #[allow(dead_code)]
-    pub fn new(value: &str) -> Self {
-        DefaultHandler {
-            value: String::from(value),
-        }
+    fn handle_request_with_source(&self, key: &str) -> Option<(String, Source)> {
+        self.handle_request(key)
+            .map(|value| (value, Source::Unknown))
     }
-}
 
-impl Handler for DefaultHandler {
-    /// Always returns the stored value, regardless of the key.
+    /// Handles a request based on the provided key, reporting where the value came from and
+    /// failures of the underlying source.
     ///
-    /// This implementation ignores the provided key and always returns the
-    /// value stored in the `DefaultHandler`.
-    fn handle_request(&self, _key: &str) -> Option<String> {
-        Some(self.value.clone())
-    }
-}
-
-impl Into<Box<dyn Handler>> for DefaultHandler {
-    fn into(self) -> Box<dyn Handler> {
-        Box::new(self)
+    /// The default implementation delegates to [`Handler::handle_request_with_source`] and never
+    /// fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key associated with the request.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(Some((value, source)))` if a value is associated with the key, `Ok(None)` if there's
+    /// no value associated with the key, or an [`Error`] if the underlying source could not be
+    /// read.
+    fn try_handle_request_with_source(&self, key: &str) -> Result<Option<(String, Source)>, Error> {
+        Ok(self.handle_request_with_source(key))
     }
-}
 
-#[cfg(feature = "clap")]
-pub mod internal_clap {
-    use super::*;
-    use clap::ArgMatches;
-    /// A handler for managing command-line arguments.
+    /// Handles a request for a key which may be associated with multiple values.
     ///
-    /// This struct is responsible for handling command-line arguments passed to the application.
-    /// If a value for a given key is not found in the arguments, it delegates the request to the
-    /// next handler (if provided).
+    /// The default implementation delegates to [`Handler::handle_request`] and returns its value
+    /// as the only element.
     ///
-    /// # Examples
+    /// # Arguments
     ///
-    /// ```
-    /// use cor_args::{ArgHandler, Handler};
+    /// * `key` - The key associated with the request.
     ///
-    /// // Create a simple `clap` command
-    /// let args = clap::Command::new("myapp")
-    ///     .arg(clap::Arg::new("example").long("example"))
-    ///     .get_matches();
+    /// # Returns
     ///
-    /// // Create a new ArgHandler for a `clap::ArgMatches`
-    /// let handler = ArgHandler::new(&args);
+    /// An `Option` wrapping all values associated with the key.
+    /// If there's no value associated with the key, it should return `None`.
+    ///
+    /// # Examples
     ///
-    /// // Add a fallback handler
-    /// //let handler = handler.next(some_other_handler.into());
+    /// ```
+    /// use cor_args::{EnvHandler, Handler};
     ///
-    /// // Handle a configuration request matching the `clap::Arg` name
-    /// let value = handler.handle_request("example");
+    /// std::env::set_var("MYAPP_include", "a,b");
+    /// let handler = EnvHandler::new().prefix("MYAPP_").delimiter(",");
+    /// let values = handler.handle_request_all("include");
+    /// assert_eq!(values, Some(vec!["a".to_string(), "b".to_string()]));
     /// ```
-    pub struct ArgHandler<'a> {
-        /// Parsed command-line arguments.
-        args: &'a ArgMatches,
-        /// An optional next handler to delegate requests if this handler can't fulfill them.
-        next: Option<Box<dyn Handler>>,
+    fn handle_request_all(&self, key: &str) -> Option<Vec<String>> {
+        self.handle_request(key).map(|value| vec![value])
     }
 
-    impl<'a> ArgHandler<'a> {
-        /// Creates a new `ArgHandler` with the specified arguments.
-        ///
-        /// # Arguments
-        ///
-        /// * `args` - The parsed command-line arguments.
-        ///
-        /// # Examples
-        ///
-        /// ```
-        /// use cor_args::ArgHandler;
-        ///
-        /// let args = clap::Command::new("myapp")
-        ///     .arg(clap::Arg::new("config").long("some-option"))
-        ///     .get_matches();
-        ///
-        /// let handler = ArgHandler::new(&args);
-        /// ```
-        #[allow(dead_code)]
-        pub fn new(args: &'a ArgMatches) -> Self {
-            ArgHandler { args, next: None }
-        }
-
-        #[allow(dead_code)]
-        pub fn next(mut self, handler: Box<dyn Handler>) -> Self {
-            self.next = Some(handler);
-            self
-        }
+    /// Handles a request for a key which may be associated with multiple values, reporting
+    /// failures of the underlying source.
+    ///
+    /// The default implementation delegates to [`Handler::handle_request_all`] and never fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key associated with the request.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(Some(values))` if values are associated with the key, `Ok(None)` if there's no value
+    /// associated with the key, or an [`Error`] if the underlying source could not be read.
+    fn try_handle_request_all(&self, key: &str) -> Result<Option<Vec<String>>, Error> {
+        Ok(self.handle_request_all(key))
     }
 
-    impl<'a> Handler for ArgHandler<'a> {
-        /// Retrieves a value for the specified key from the command-line arguments.
-        ///
-        /// If the key is not found in the arguments, and if a next handler is provided, it delegates the request
-        /// to the next handler. If there's no next handler or if the key is not found in both the arguments and
-        /// the next handler, it returns `None`.
-        ///
-        /// # Arguments
-        ///
-        /// * `key` - The key for which the value needs to be retrieved.
-        ///
-        /// # Returns
-        ///
-        /// An `Option` containing the value associated with the key, or `None` if the key is not found.
-        fn handle_request(&self, key: &str) -> Option<String> {
-            if let Ok(value) = self.args.try_get_one::<String>(key) {
-                if let Some(value) = value {
-                    return Some(value.clone());
-                }
-            }
-            if let Some(next_handler) = &self.next {
-                return next_handler.handle_request(key);
-            }
-            None
-        }
+    /// Enumerates the keys this handler, and any handlers it delegates to, can answer.
+    ///
+    /// The default implementation returns `None`, meaning the handler cannot enumerate its keys,
+    /// e.g. because it answers any key like the [`DefaultHandler`].
+    ///
+    /// # Returns
+    ///
+    /// An `Option` wrapping the keys known to the handler without duplicates, or `None` if the
+    /// handler cannot enumerate its keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::{EnvHandler, Handler};
+    ///
+    /// std::env::set_var("MYAPP_port", "8080");
+    /// let handler = EnvHandler::new().prefix("MYAPP_");
+    /// let keys = handler.keys().unwrap();
+    /// assert!(keys.contains(&"port".to_string()));
+    /// ```
+    fn keys(&self) -> Option<Vec<String>> {
+        None
     }
 
-    impl<'a> Into<Box<dyn Handler + 'a>> for ArgHandler<'a> {
-        fn into(self) -> Box<dyn Handler + 'a> {
-            Box::new(self)
-        }
+    /// Returns how a [`Chain`] treats a failure of this handler's source.
+    ///
+    /// A missing key always falls through to the next handler. The default implementation
+    /// returns [`ErrorPolicy::Fail`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::{ErrorPolicy, Handler, JSONFileHandler};
+    ///
+    /// let handler = JSONFileHandler::new("file.json").on_error(ErrorPolicy::Warn);
+    /// assert_eq!(handler.error_policy(), ErrorPolicy::Warn);
+    /// ```
+    fn error_policy(&self) -> ErrorPolicy {
+        ErrorPolicy::default()
     }
-}
-
-/// A handler for retrieving values from environment variables.
-///
-/// This struct is responsible for handling requests by checking for the existence of
-/// an environment variable corresponding to the provided key. If the environment variable
-/// is not found, it delegates the request to the next handler (if provided).
-///
-/// # Examples
-///
-/// ```
-/// use cor_args::{EnvHandler, Handler};
-///
-/// // Create a new EnvHandler specifying a prefix for environment variables
-/// let handler = EnvHandler::new().prefix("MYAPP_");
-///
-/// // Add a fallback handler
-/// //let handler = handler.next(some_other_handler.into());
-///
-/// // Handle a configuration request matching `MYAPP_some_key`
-/// let value = handler.handle_request("some_key");
-/// ```
-pub struct EnvHandler<'a> {
-    /// A prefix to prepend to the key passed to `handle_request()`.
-    prefix: Option<Cow<'a, str>>,
-    /// An optional next handler to delegate requests if this handler can't fulfill them.
-    next: Option<Box<dyn Handler>>,
-}
 
-impl<'a> EnvHandler<'a> {
-    /// Creates a new `EnvHandler`.
+    /// Retrieves the value for the provided key and parses it into `T`.
+    ///
+    /// The raw value is parsed using `T`'s [`FromStr`] implementation.
     ///
     /// # Arguments
     ///
-    /// * `prefix` - An optional prefix to which requests will prepend when `handle_request()` is executed.` If `None`, an empty string is assigned.
+    /// * `key` - The key associated with the request.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(None)` if there's no value associated with the key, `Ok(Some(value))` if the value
+    /// was parsed successfully, or an [`Error::InvalidValue`] describing the key, the raw value
+    /// and the expected type if parsing failed. Failures of the underlying source are reported as
+    /// returned by [`Handler::try_handle_request`].
     ///
     /// # Examples
     ///
     /// ```
-    /// use cor_args::EnvHandler;
+    /// use cor_args::{DefaultHandler, Handler};
     ///
-    /// let handler = EnvHandler::new();
+    /// let handler = DefaultHandler::new("8080");
+    /// let port = handler.get::<u16>("port").unwrap();
+    /// assert_eq!(port, Some(8080));
     /// ```
-    #[allow(dead_code)]
-    pub fn new() -> Self {
-        EnvHandler {
-            prefix: None,
-            next: None,
-        }
-    }
-
-    #[allow(dead_code)]
-    pub fn next(mut self, handler: Box<dyn Handler>) -> Self {
-        self.next = Some(handler);
-        self
-    }
-
-    #[allow(dead_code)]
-    pub fn prefix<S>(mut self, prefix: S) -> Self
+    fn get<T>(&self, key: &str) -> Result<Option<T>, Error>
     where
-        S: Into<Cow<'a, str>>,
+        Self: Sized,
+        T: FromStr,
+        T::Err: fmt::Display,
     {
-        self.prefix = Some(prefix.into());
-        self
+        match self.try_handle_request(key)? {
+            Some(value) => match value.parse::<T>() {
+                Ok(parsed) => Ok(Some(parsed)),
+                Err(e) => Err(Error::invalid_value::<T>(key, value, e)),
+            },
+            None => Ok(None),
+        }
     }
-}
 
-impl<'a> Handler for EnvHandler<'a> {
-    /// Retrieves a value for the specified key from the environment variables.
+    /// Retrieves the value for the provided key and deserializes it into `T` using serde.
     ///
-    /// If the environment variable corresponding to the key is not found, and if a next handler is provided,
-    /// it delegates the request to the next handler. If there's no next handler or if the key is not found
-    /// both in the environment and the next handler, it returns `None`.
+    /// The raw value is first interpreted as JSON, which allows values such as arrays and
+    /// objects returned by the [`JSONFileHandler`] to be deserialized. If the raw value is not
+    /// valid JSON, or `T` cannot be deserialized from it, it is treated as a JSON string, so
+    /// e.g. `8080` can be retrieved as a `String` as well as a `u16`.
     ///
     /// # Arguments
     ///
-    /// * `key` - The key for which the value needs to be retrieved from environment variables.
+    /// * `key` - The key associated with the request.
     ///
     /// # Returns
     ///
-    /// An `Option` containing the value associated with the key, or `None` if the key is not found.
-    fn handle_request(&self, key: &str) -> Option<String> {
-        if let Some(prefix) = &self.prefix {
-            let key = format!("{prefix}{key}");
-            if let Ok(value) = env::var(key) {
-                return Some(value);
-            }
-        } else {
-            if let Ok(value) = env::var(key) {
-                return Some(value);
+    /// `Ok(None)` if there's no value associated with the key, `Ok(Some(value))` if the value
+    /// was deserialized successfully, or an [`Error::InvalidValue`] otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::{DefaultHandler, Handler};
+    ///
+    /// let handler = DefaultHandler::new("[1, 2, 3]");
+    /// let values = handler.get_deserialized::<Vec<u8>>("values").unwrap();
+    /// assert_eq!(values, Some(vec![1, 2, 3]));
+    /// ```
+    fn get_deserialized<T>(&self, key: &str) -> Result<Option<T>, Error>
+    where
+        Self: Sized,
+        T: DeserializeOwned,
+    {
+        match self.try_handle_request(key)? {
+            Some(value) => {
+                let as_string = || serde_json::from_value::<T>(Value::String(value.clone()));
+                let parsed = match serde_json::from_str::<Value>(&value) {
+                    Ok(json) => {
+                        serde_json::from_value::<T>(json).or_else(|e| as_string().map_err(|_| e))
+                    }
+                    Err(_) => as_string(),
+                };
+                match parsed {
+                    Ok(parsed) => Ok(Some(parsed)),
+                    Err(e) => Err(Error::invalid_value::<T>(key, value, e)),
+                }
             }
+            None => Ok(None),
         }
-        if let Some(next_handler) = &self.next {
-            return next_handler.handle_request(key);
-        }
-        None
     }
 }
 
-impl<'a> Into<Box<dyn Handler + 'a>> for EnvHandler<'a> {
-    fn into(self) -> Box<dyn Handler + 'a> {
-        Box::new(self)
+impl<H: Handler + ?Sized> Handler for Box<H> {
+    fn handle_request(&self, key: &str) -> Option<String> {
+        (**self).handle_request(key)
+    }
+
+    fn try_handle_request(&self, key: &str) -> Result<Option<String>, Error> {
+        (**self).try_handle_request(key)
+    }
+
+    fn handle_request_with_source(&self, key: &str) -> Option<(String, Source)> {
+        (**self).handle_request_with_source(key)
+    }
+
+    fn try_handle_request_with_source(&self, key: &str) -> Result<Option<(String, Source)>, Error> {
+        (**self).try_handle_request_with_source(key)
+    }
+
+    fn handle_request_all(&self, key: &str) -> Option<Vec<String>> {
+        (**self).handle_request_all(key)
+    }
+
+    fn try_handle_request_all(&self, key: &str) -> Result<Option<Vec<String>>, Error> {
+        (**self).try_handle_request_all(key)
+    }
+
+    fn keys(&self) -> Option<Vec<String>> {
+        (**self).keys()
+    }
+
+    fn error_policy(&self) -> ErrorPolicy {
+        (**self).error_policy()
     }
 }
 
-/// A handler for retrieving values from a file.
-///
-/// This struct is responsible for handling requests by checking for values within a specified file.
-///
-/// # Examples
-///
-/// ```
-/// use cor_args::{FileHandler, Handler};
-///
-/// // Create a new FileHandler specifying a path to a file.
-/// let handler = FileHandler::new("/path/to/file");
+impl<H: Handler + ?Sized> Handler for &H {
+    fn handle_request(&self, key: &str) -> Option<String> {
+        (**self).handle_request(key)
+    }
+
+    fn try_handle_request(&self, key: &str) -> Result<Option<String>, Error> {
+        (**self).try_handle_request(key)
+    }
+
+    fn handle_request_with_source(&self, key: &str) -> Option<(String, Source)> {
+        (**self).handle_request_with_source(key)
+    }
+
+    fn try_handle_request_with_source(&self, key: &str) -> Result<Option<(String, Source)>, Error> {
+        (**self).try_handle_request_with_source(key)
+    }
+
+    fn handle_request_all(&self, key: &str) -> Option<Vec<String>> {
+        (**self).handle_request_all(key)
+    }
+
+    fn try_handle_request_all(&self, key: &str) -> Result<Option<Vec<String>>, Error> {
+        (**self).try_handle_request_all(key)
+    }
+
+    fn keys(&self) -> Option<Vec<String>> {
+        (**self).keys()
+    }
+
+    fn error_policy(&self) -> ErrorPolicy {
+        (**self).error_policy()
+    }
+}
+
+impl<H: Handler + ?Sized> Handler for Arc<H> {
+    fn handle_request(&self, key: &str) -> Option<String> {
+        (**self).handle_request(key)
+    }
+
+    fn try_handle_request(&self, key: &str) -> Result<Option<String>, Error> {
+        (**self).try_handle_request(key)
+    }
+
+    fn handle_request_with_source(&self, key: &str) -> Option<(String, Source)> {
+        (**self).handle_request_with_source(key)
+    }
+
+    fn try_handle_request_with_source(&self, key: &str) -> Result<Option<(String, Source)>, Error> {
+        (**self).try_handle_request_with_source(key)
+    }
+
+    fn handle_request_all(&self, key: &str) -> Option<Vec<String>> {
+        (**self).handle_request_all(key)
+    }
+
+    fn try_handle_request_all(&self, key: &str) -> Result<Option<Vec<String>>, Error> {
+        (**self).try_handle_request_all(key)
+    }
+
+    fn keys(&self) -> Option<Vec<String>> {
+        (**self).keys()
+    }
+
+    fn error_policy(&self) -> ErrorPolicy {
+        (**self).error_policy()
+    }
+}
+
+/// Merges the keys of two handlers, preserving order and removing duplicates.
 ///
-/// // Add a fallback handler
-/// //let handler = handler.next(some_other_handler.into());
+/// Returns `None` only if neither handler can enumerate its keys.
+fn merge_keys(keys: Option<Vec<String>>, other: Option<Vec<String>>) -> Option<Vec<String>> {
+    match (keys, other) {
+        (None, None) => None,
+        (keys, other) => {
+            let mut merged: Vec<String> = Vec::new();
+            for key in keys.into_iter().chain(other).flatten() {
+                if !merged.contains(&key) {
+                    merged.push(key);
+                }
+            }
+            Some(merged)
+        }
+    }
+}
+
+/// Describes the origin of a value returned by a [`Handler`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Source {
+    /// A command-line argument, identified by its `clap` argument id.
+    Arg {
+        /// The id of the `clap::Arg`.
+        id: String,
+    },
+    /// An environment variable.
+    Env {
+        /// The full name of the variable, including any prefix.
+        var: String,
+    },
+    /// The content of a file.
+    File {
+        /// The path of the file.
+        path: PathBuf,
+    },
+    /// A key within a structured file, such as a JSON file.
+    FileKey {
+        /// The format of the file, e.g. `"JSON"`.
+        format: &'static str,
+        /// The path of the file.
+        path: PathBuf,
+        /// The dotted path of the key within the file, e.g. `"server.port"`.
+        key: String,
+    },
+    /// A key within a `config::Config`.
+    Config {
+        /// The dotted path of the key within the configuration, e.g. `"server.port"`.
+        key: String,
+    },
+    /// The value of a `DefaultHandler`.
+    Default,
+    /// A handler which does not describe where its values come from.
+    Unknown,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Arg { id } => write!(f, "command-line argument `{id}`"),
+            Source::Env { var } => write!(f, "environment variable `{var}`"),
+            Source::File { path } => write!(f, "file {}", path.display()),
+            Source::FileKey { format, path, key } => {
+                write!(f, "key `{key}` in {format} file {}", path.display())
+            }
+            Source::Config { key } => write!(f, "configuration key `{key}`"),
+            Source::Default => write!(f, "default value"),
+            Source::Unknown => write!(f, "unknown source"),
+        }
+    }
+}
+
+/// Describes how a [`Chain`] treats a handler whose source fails, e.g. a file which cannot be read
+/// or parsed.
 ///
-/// // Handle a configuration request returning contents of `/path/to/file`
-/// let value = handler.handle_request("");
-/// ```
-pub struct FileHandler {
-    /// Path to the file from which values are to be retrieved.
-    file_path: PathBuf,
-    /// An optional next handler to delegate requests if this handler can't fulfill them.
-    next: Option<Box<dyn Handler>>,
+/// A handler which has no value for a key always passes the request on to the next handler. The
+/// policy decides what happens when a chain is queried with [`Handler::try_handle_request`].
+/// Methods which cannot report errors, such as [`Handler::handle_request`], always pass the
+/// request on, logging the error as a warning unless the policy is
+/// [`ErrorPolicy::FallThrough`], so a chain ending with a [`DefaultHandler`] always has a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    /// Treat the failure like a missing key and pass the request on to the next handler.
+    FallThrough,
+    /// Stop the chain and return the error, or log it as a warning and pass the request on to the
+    /// next handler if the chain cannot report errors. This is the default.
+    #[default]
+    Fail,
+    /// Log the error as a warning and pass the request on to the next handler.
+    Warn,
 }
 
-impl FileHandler {
-    /// Creates a new `FileHandler` with the specified file path.
-    ///
-    /// # Arguments
+impl ErrorPolicy {
+    /// Applies the policy to the result of a request for `key`.
     ///
-    /// * `file_path` - The path to the file from which values are to be retrieved.
+    /// # Returns
     ///
-    /// # Examples
+    /// The error if the chain should stop, otherwise the value of the request, if any.
+    fn apply<T>(self, key: &str, result: Result<Option<T>, Error>) -> Result<Option<T>, Error> {
+        match (self, result) {
+            (_, Ok(value)) => Ok(value),
+            (ErrorPolicy::Fail, Err(e)) => Err(e),
+            (ErrorPolicy::FallThrough, Err(_)) => Ok(None),
+            (ErrorPolicy::Warn, Err(e)) => {
+                log::warn!("{e}; ignoring the source for key `{key}`");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Applies the policy to the result of a request for `key` made by a method which cannot
+    /// report errors.
     ///
-    /// ```
-    /// use cor_args::FileHandler;
+    /// # Returns
     ///
-    /// let handler = FileHandler::new("/path/to/file");
-    /// ```
-    #[allow(dead_code)]
-    pub fn new<P>(file_path: P) -> Self
-    where
-        P: Into<PathBuf>,
-    {
-        FileHandler {
-            file_path: file_path.into(),
-            next: None,
+    /// The value of the request, if any. A failure is passed on to the next handler as `None`.
+    fn apply_infallible<T>(self, key: &str, result: Result<Option<T>, Error>) -> Option<T> {
+        match self {
+            ErrorPolicy::FallThrough => result.ok().flatten(),
+            ErrorPolicy::Fail | ErrorPolicy::Warn => ErrorPolicy::Warn.apply(key, result).ok()?,
         }
     }
+}
 
-    #[allow(dead_code)]
-    pub fn next(mut self, handler: Box<dyn Handler>) -> Self {
-        self.next = Some(handler);
-        self
+/// Errors that can occur while retrieving values from a [`Handler`].
+#[derive(Debug)]
+pub enum Error {
+    /// A value was found but could not be converted into the requested type.
+    InvalidValue {
+        /// The key that was requested.
+        key: String,
+        /// The raw value returned by the handler.
+        value: String,
+        /// The name of the type the value was expected to parse as.
+        expected: &'static str,
+        /// The reason the conversion failed.
+        reason: String,
+    },
+    /// A file could not be read.
+    Io {
+        /// The path of the file that could not be read.
+        path: PathBuf,
+        /// The underlying I/O error.
+        source: io::Error,
+    },
+    /// The content of a file could not be parsed.
+    Parse {
+        /// The path of the file that could not be parsed.
+        path: PathBuf,
+        /// The reason parsing failed.
+        reason: String,
+    },
+    /// A handler's configuration source could not be deserialized.
+    Deserialize {
+        /// The name of the handler whose source failed.
+        handler: String,
+        /// The reason deserialization failed.
+        reason: String,
+    },
+    /// A key was found at more than one location and the handler cannot tell which one is meant.
+    AmbiguousKey {
+        /// The key that was requested.
+        key: String,
+        /// The path of the file in which the key is ambiguous.
+        path: PathBuf,
+        /// The full paths of all locations at which the key was found.
+        candidates: Vec<String>,
+    },
+    /// A required key has no value.
+    MissingKey {
+        /// The full, dotted key which has no value, e.g. `"server.port"`.
+        key: String,
+    },
+    /// Any other error, e.g. one reported by a serde `Deserialize` implementation.
+    Message(String),
+}
+
+impl Error {
+    fn invalid_value<T>(key: &str, value: String, reason: impl fmt::Display) -> Self {
+        Error::InvalidValue {
+            key: key.to_string(),
+            value,
+            expected: std::any::type_name::<T>(),
+            reason: reason.to_string(),
+        }
+    }
+
+    fn io<P: Into<PathBuf>>(path: P, source: io::Error) -> Self {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    fn parse<P: Into<PathBuf>>(path: P, reason: impl fmt::Display) -> Self {
+        Error::Parse {
+            path: path.into(),
+            reason: reason.to_string(),
+        }
     }
 }
 
-impl Handler for FileHandler {
-    /// Retrieves content from the specified file.
-    ///
-    /// This implementation attempts to read content from the file specified by `file_path`.
-    /// If reading fails, and if a next handler is provided, it delegates the request
-    /// to the next handler. If there's no next handler or if the file reading fails,
-    /// it returns `None`.
-    ///
-    /// # Arguments
-    ///
-    /// * `key` - The key for which the value needs to be retrieved. (Note: The `key` is currently not used directly, just passed on to the next handler.)
-    ///
-    /// # Returns
-    ///
-    /// An `Option` containing the contents of the file, or `None` if the key is not found.
-    fn handle_request(&self, key: &str) -> Option<String> {
-        if let Ok(mut file) = File::open(&self.file_path) {
-            let mut content = String::new();
-            if let Ok(_byte_count) = file.read_to_string(&mut content) {
-                return Some(content);
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidValue {
+                key,
+                value,
+                expected,
+                reason,
+            } => write!(
+                f,
+                "invalid value {value:?} for key `{key}`: expected {expected} ({reason})"
+            ),
+            Error::Io { path, source } => {
+                write!(f, "failed to read {}: {source}", path.display())
             }
+            Error::Parse { path, reason } => {
+                write!(f, "failed to parse {}: {reason}", path.display())
+            }
+            Error::Deserialize { handler, reason } => {
+                write!(f, "{handler} failed to deserialize its source: {reason}")
+            }
+            Error::AmbiguousKey {
+                key,
+                path,
+                candidates,
+            } => write!(
+                f,
+                "ambiguous key `{key}` in {}: found at {}",
+                path.display(),
+                candidates.join(", ")
+            ),
+            Error::MissingKey { key } => write!(f, "missing value for key `{key}`"),
+            Error::Message(message) => write!(f, "{message}"),
         }
-        if let Some(next_handler) = &self.next {
-            return next_handler.handle_request(key);
-        }
-        None
     }
 }
 
-impl Into<Box<dyn Handler>> for FileHandler {
-    fn into(self) -> Box<dyn Handler> {
-        Box::new(self)
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
     }
 }
 
-/// A handler for retrieving values from a specified JSON file.
+/// An ordered list of handlers which are queried in turn.
 ///
-/// This struct is responsible for handling requests by reading content from the file
-/// specified in the underlying `FileHandler`, and then searching for a specific key
-/// within the parsed JSON structure. If the key is not found in the JSON structure,
-/// it delegates the request to the next handler (if provided).
+/// A request is passed to each handler in order until one of them returns a value. The order of
+/// the handlers can be changed after construction, and a `Chain` is itself a `Handler`, so chains
+/// can be nested.
 ///
-/// ```
-/// use cor_args::{JSONFileHandler, Handler};
+/// Handlers which borrow data, such as an `ArgHandler` borrowing `clap::ArgMatches`, can be placed
+/// at any position; the chain cannot outlive the borrowed data.
 ///
-/// // Create a new JSONFileHandler specifying a path to a file.
-/// let handler = JSONFileHandler::new("file.json");
+/// # Examples
+///
+/// ```
+/// use cor_args::{Chain, DefaultHandler, EnvHandler, FileHandler, Handler};
 ///
-/// // Add a fallback handler
-/// //let handler = handler.next(some_other_handler.into());
+/// // Create a new Chain querying the environment, then a file, then falling back to a default
+/// let handler = Chain::new()
+///     .with(EnvHandler::new())
+///     .with(FileHandler::new("/path/to/file"))
+///     .with(DefaultHandler::new("some_value"));
 ///
-/// // Handle a configuration request matching a `"some_key"` within `file.json`
+/// // Handle a configuration request
 /// let value = handler.handle_request("some_key");
 /// ```
-pub struct JSONFileHandler {
-    /// Underlying file handler used to read content from the specified file.
-    file_handler: FileHandler,
+#[derive(Default)]
+pub struct Chain<'a> {
+    /// The handlers in the order in which they are queried.
+    handlers: Vec<Box<dyn Handler + 'a>>,
 }
 
-impl JSONFileHandler {
-    /// Creates a new `JSONFileHandler` with the specified file path.
+impl<'a> Chain<'a> {
+    /// Creates a new, empty `Chain`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::Chain;
+    ///
+    /// let chain = Chain::new();
+    /// ```
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Chain {
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Appends a handler to the end of the chain, returning the chain.
     ///
     /// # Arguments
     ///
-    /// * `file_path` - The path to the JSON file from which values are to be retrieved.
+    /// * `handler` - The handler to query after all handlers already in the chain.
     ///
     /// # Examples
     ///
     /// ```
-    /// use cor_args::FileHandler;
+    /// use cor_args::{Chain, DefaultHandler, EnvHandler};
     ///
-    /// let handler = FileHandler::new("file.json");
+    /// let chain = Chain::new()
+    ///     .with(EnvHandler::new())
+    ///     .with(DefaultHandler::new("some_value"));
     /// ```
     #[allow(dead_code)]
-    pub fn new<P>(file_path: P) -> Self
-    where
-        P: Into<PathBuf>,
-    {
-        JSONFileHandler {
-            file_handler: FileHandler::new(file_path),
-        }
+    pub fn with<H: Handler + 'a>(mut self, handler: H) -> Self {
+        self.push(handler);
+        self
     }
 
+    /// Appends a handler to the end of the chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - The handler to query after all handlers already in the chain.
     #[allow(dead_code)]
-    pub fn next(mut self, handler: Box<dyn Handler>) -> Self {
-        self.file_handler.next = Some(handler);
-        self
+    pub fn push<H: Handler + 'a>(&mut self, handler: H) {
+        self.handlers.push(Box::new(handler));
     }
 
-    /// Recursively searches for a key within the parsed JSON structure.
+    /// Inserts a handler so that it is queried before the handler currently at `index`.
     ///
     /// # Arguments
     ///
-    /// * `json_value` - The current JSON value being inspected.
-    /// * `key` - The key for which the value needs to be retrieved.
+    /// * `index` - The position of the handler which should be queried after `handler`.
+    /// * `handler` - The handler to insert.
     ///
-    /// # Returns
+    /// # Panics
     ///
-    /// If found, returns an `Option` wrapping a `String` value associated with the key.
-    /// Otherwise, returns `None`.
-    pub fn find_key_recursive(json_value: &Value, key: &str) -> Option<String> {
-        match json_value {
-            Value::Object(map) => {
-                if let Some(value) = map.get(key) {
-                    match value {
-                        serde_json::Value::String(value) => {
-                            return Some(value.as_str().to_string())
-                        }
-                        _ => return Some(value.to_string()),
-                    }
-                }
-                for (_, value) in map.iter() {
-                    if let Some(found) = Self::find_key_recursive(value, key) {
-                        return Some(found);
-                    }
-                }
-            }
-            Value::Array(arr) => {
-                for value in arr.iter() {
-                    if let Some(found) = Self::find_key_recursive(value, key) {
-                        return Some(found);
-                    }
-                }
-            }
-            _ => {}
-        }
-        None
+    /// Panics if `index` is greater than the number of handlers in the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::{Chain, DefaultHandler, EnvHandler, Handler};
+    ///
+    /// let mut chain = Chain::new().with(DefaultHandler::new("some_value"));
+    /// chain.insert_before(0, EnvHandler::new());
+    /// assert_eq!(chain.len(), 2);
+    /// ```
+    #[allow(dead_code)]
+    pub fn insert_before<H: Handler + 'a>(&mut self, index: usize, handler: H) {
+        self.handlers.insert(index, Box::new(handler));
     }
-}
 
-impl Handler for JSONFileHandler {
-    /// Retrieves a value for the specified key from the JSON file.
-    ///
-    /// This implementation attempts to read content from the file specified in the underlying `FileHandler`,
-    /// parses the content as JSON, and then searches for the specified key within the parsed JSON structure.
-    /// If the key is not found in the JSON structure, and if a next handler is provided, it delegates the request
-    /// to the next handler. If there's no next handler, or if the key is not found in both the JSON structure
-    /// and the next handler, it returns `None`.
+    /// Removes and returns the handler at `index`.
     ///
     /// # Arguments
     ///
-    /// * `key` - The key for which the value needs to be retrieved from the JSON file.
+    /// * `index` - The position of the handler to remove.
     ///
     /// # Returns
     ///
-    /// An `Option` containing the value associated with the key, or `None` if the key is not found.
-    fn handle_request(&self, key: &str) -> Option<String> {
-        if let Some(file_data) = self.file_handler.handle_request(key) {
-            if let Ok(parsed_json) = serde_json::from_str::<Value>(&file_data) {
-                if let Some(value) = Self::find_key_recursive(&parsed_json, key) {
-                    return Some(value);
-                }
-            } else {
-                if let Some(next_handler) = &self.file_handler.next {
-                    return next_handler.handle_request(key);
-                }
-            }
+    /// The removed handler, or `None` if `index` is out of bounds.
+    #[allow(dead_code)]
+    pub fn remove(&mut self, index: usize) -> Option<Box<dyn Handler + 'a>> {
+        if index < self.handlers.len() {
+            Some(self.handlers.remove(index))
+        } else {
+            None
         }
-        None
     }
-}
 
-impl Into<Box<dyn Handler>> for JSONFileHandler {
-    fn into(self) -> Box<dyn Handler> {
-        Box::new(self)
+    /// Returns an iterator over the handlers in the order in which they are queried.
+    #[allow(dead_code)]
+    pub fn iter(&self) -> impl Iterator<Item = &(dyn Handler + 'a)> {
+        self.handlers.iter().map(|handler| handler.as_ref())
+    }
+
+    /// Returns the number of handlers in the chain.
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.handlers.len()
+    }
+
+    /// Returns `true` if the chain contains no handlers.
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
     }
 }
 
-#[cfg(feature = "config")]
-pub mod internal_config {
-    use super::*;
-    use config::Config;
-    /// A configuration file handler for reading key-value pairs from a file.
-    ///
-    /// The `ConfigHandler` is used to read configuration data from a file and provide it
-    /// as key-value pairs. It supports chaining multiple handlers for fallback behavior.
-    ///
-    /// # Examples
+impl<'a> Handler for Chain<'a> {
+    /// Returns the value of the first handler which has a value for the key.
     ///
-    /// ```
-    /// use cor_args::{ConfigHandler, Handler};
+    /// A handler which fails is skipped, as described for [`ErrorPolicy`].
+    fn handle_request(&self, key: &str) -> Option<String> {
+        Chain::find_in(self.iter(), key, |handler| handler.try_handle_request(key))
+    }
+
+    /// Returns the value of the first handler which has a value for the key.
     ///
-    /// // Example YAML file
-    /// // ---
-    /// // test_obj:
-    /// //     some_key: "test_val"
+    /// If a handler fails before a value is found, its [`ErrorPolicy`] decides whether its
+    /// error is returned or the request is passed on to the remaining handlers.
+    fn try_handle_request(&self, key: &str) -> Result<Option<String>, Error> {
+        Chain::try_find_in(self.iter(), key, |handler| handler.try_handle_request(key))
+    }
+
+    /// Returns the value and [`Source`] of the first handler which has a value for the key.
     ///
-    /// let config = config::Config::builder().build().unwrap();
-    /// //    .add_source(config::File::new("/path/to/file",
-    /// //        config::FileFormat::Yaml,
-    /// //    ))
-    /// //    .build()
-    /// //    .unwrap();
+    /// Failing handlers are treated as described for [`Chain::handle_request`].
+    fn handle_request_with_source(&self, key: &str) -> Option<(String, Source)> {
+        Chain::find_in(self.iter(), key, |handler| {
+            handler.try_handle_request_with_source(key)
+        })
+    }
+
+    /// Returns the value and [`Source`] of the first handler which has a value for the key.
     ///
-    /// // Create a new ConfigHandler for a specific config::Config instance
-    /// let handler = ConfigHandler::new(Box::new(config));
+    /// Failing handlers are treated as described for [`Chain::try_handle_request`].
+    fn try_handle_request_with_source(&self, key: &str) -> Result<Option<(String, Source)>, Error> {
+        Chain::try_find_in(self.iter(), key, |handler| {
+            handler.try_handle_request_with_source(key)
+        })
+    }
+
+    /// Returns all values of the first handler which has a value for the key.
     ///
-    /// // Add a fallback handler
-    /// //let handler = handler.next(some_other_handler.into());
+    /// Failing handlers are treated as described for [`Chain::handle_request`].
+    fn handle_request_all(&self, key: &str) -> Option<Vec<String>> {
+        Chain::find_in(self.iter(), key, |handler| {
+            handler.try_handle_request_all(key)
+        })
+    }
+
+    /// Returns all values of the first handler which has a value for the key.
     ///
-    /// // Handle a configuration request
-    /// let value = handler.handle_request("some_key");
-    /// ```
-    pub struct ConfigHandler {
-        /// The Config instance ultimately being queried.
-        config: Box<config::Config>,
-        next: Option<Box<dyn Handler>>,
+    /// Failing handlers are treated as described for [`Chain::try_handle_request`].
+    fn try_handle_request_all(&self, key: &str) -> Result<Option<Vec<String>>, Error> {
+        Chain::try_find_in(self.iter(), key, |handler| {
+            handler.try_handle_request_all(key)
+        })
     }
 
-    impl ConfigHandler {
-        /// Create a new `ConfigHandler` for the specified file path.
-        ///
-        /// # Parameters
-        ///
-        /// - `config`: A `config::Config` reference.
-        ///
-        /// # Returns
-        ///
-        /// A new `ConfigHandler` instance.
-        ///
-        /// # Examples
-        ///
-        /// ```
-        /// use cor_args::ConfigHandler;
-        ///
-        /// let handler = ConfigHandler::new(Box::new(config::Config::builder().build().unwrap()));
-        /// ```
-        #[allow(dead_code)]
-        pub fn new(config: Box<Config>) -> Self {
-            ConfigHandler { config, next: None }
+    /// Returns the union of the keys of all handlers which can enumerate their keys.
+    fn keys(&self) -> Option<Vec<String>> {
+        Chain::keys_in(self.iter())
+    }
+}
+
+impl Chain<'_> {
+    /// Queries `handlers` in turn with `query` until one has a value, passing failures on to the
+    /// next handler as described for [`ErrorPolicy`].
+    fn find_in<'h, H, T>(
+        mut handlers: impl Iterator<Item = &'h H>,
+        key: &str,
+        query: impl Fn(&'h H) -> Result<Option<T>, Error>,
+    ) -> Option<T>
+    where
+        H: Handler + ?Sized + 'h,
+    {
+        handlers.find_map(|handler| handler.error_policy().apply_infallible(key, query(handler)))
+    }
+
+    /// Queries `handlers` in turn with `query` until one has a value, applying the
+    /// [`ErrorPolicy`] of each handler to its failures.
+    fn try_find_in<'h, H, T>(
+        handlers: impl Iterator<Item = &'h H>,
+        key: &str,
+        query: impl Fn(&'h H) -> Result<Option<T>, Error>,
+    ) -> Result<Option<T>, Error>
+    where
+        H: Handler + ?Sized + 'h,
+    {
+        for handler in handlers {
+            if let Some(found) = handler.error_policy().apply(key, query(handler))? {
+                return Ok(Some(found));
+            }
+        }
+        Ok(None)
+    }
+
+    fn keys_in<'h, H>(handlers: impl Iterator<Item = &'h H>) -> Option<Vec<String>>
+    where
+        H: Handler + ?Sized + 'h,
+    {
+        handlers.fold(None, |keys, handler| merge_keys(keys, handler.keys()))
+    }
+}
+
+/// A [`Chain`] of `Send + Sync` handlers which can be cheaply cloned and queried from many
+/// threads.
+///
+/// Requests are answered exactly as by a [`Chain`]. A `SharedChain` is itself a `Handler`, so it
+/// can also be used as a member of other chains.
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{DefaultHandler, EnvHandler, Handler, SharedChain};
+///
+/// let handler = SharedChain::new()
+///     .with(EnvHandler::new())
+///     .with(DefaultHandler::new("some_value"));
+///
+/// let worker = {
+///     let handler = handler.clone();
+///     std::thread::spawn(move || handler.handle_request("some_key"))
+/// };
+/// assert_eq!(worker.join().unwrap(), Some("some_value".to_string()));
+/// ```
+#[derive(Clone, Default)]
+pub struct SharedChain {
+    /// The handlers in the order in which they are queried.
+    handlers: Arc<Vec<Arc<dyn Handler + Send + Sync>>>,
+}
+
+impl SharedChain {
+    /// Creates a new, empty `SharedChain`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::SharedChain;
+    ///
+    /// let chain = SharedChain::new();
+    /// ```
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        SharedChain {
+            handlers: Arc::new(Vec::new()),
+        }
+    }
+
+    /// Appends a handler to the end of the chain, returning the chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - The handler to query after all handlers already in the chain.
+    #[allow(dead_code)]
+    pub fn with<H: Handler + Send + Sync + 'static>(mut self, handler: H) -> Self {
+        self.push(handler);
+        self
+    }
+
+    /// Appends a handler to the end of the chain.
+    ///
+    /// Clones of the chain made before the handler is appended are not affected.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - The handler to query after all handlers already in the chain.
+    #[allow(dead_code)]
+    pub fn push<H: Handler + Send + Sync + 'static>(&mut self, handler: H) {
+        Arc::make_mut(&mut self.handlers).push(Arc::new(handler));
+    }
+
+    /// Returns the number of handlers in the chain.
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.handlers.len()
+    }
+
+    /// Returns `true` if the chain contains no handlers.
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &(dyn Handler + Send + Sync)> {
+        self.handlers.iter().map(|handler| handler.as_ref())
+    }
+}
+
+impl Handler for SharedChain {
+    /// Returns the value of the first handler which has a value for the key.
+    ///
+    /// See [`Chain`].
+    fn handle_request(&self, key: &str) -> Option<String> {
+        Chain::find_in(self.iter(), key, |handler| handler.try_handle_request(key))
+    }
+
+    /// Returns the value of the first handler which has a value for the key.
+    ///
+    /// See [`Chain`].
+    fn try_handle_request(&self, key: &str) -> Result<Option<String>, Error> {
+        Chain::try_find_in(self.iter(), key, |handler| handler.try_handle_request(key))
+    }
+
+    /// Returns the value and [`Source`] of the first handler which has a value for the key.
+    fn handle_request_with_source(&self, key: &str) -> Option<(String, Source)> {
+        Chain::find_in(self.iter(), key, |handler| {
+            handler.try_handle_request_with_source(key)
+        })
+    }
+
+    /// Returns the value and [`Source`] of the first handler which has a value for the key.
+    fn try_handle_request_with_source(&self, key: &str) -> Result<Option<(String, Source)>, Error> {
+        Chain::try_find_in(self.iter(), key, |handler| {
+            handler.try_handle_request_with_source(key)
+        })
+    }
+
+    /// Returns all values of the first handler which has a value for the key.
+    fn handle_request_all(&self, key: &str) -> Option<Vec<String>> {
+        Chain::find_in(self.iter(), key, |handler| {
+            handler.try_handle_request_all(key)
+        })
+    }
+
+    /// Returns all values of the first handler which has a value for the key.
+    fn try_handle_request_all(&self, key: &str) -> Result<Option<Vec<String>>, Error> {
+        Chain::try_find_in(self.iter(), key, |handler| {
+            handler.try_handle_request_all(key)
+        })
+    }
+
+    /// Returns the union of the keys of all handlers which can enumerate their keys.
+    fn keys(&self) -> Option<Vec<String>> {
+        Chain::keys_in(self.iter())
+    }
+}
+
+impl<'a> From<SharedChain> for Box<dyn Handler + 'a> {
+    fn from(handler: SharedChain) -> Self {
+        Box::new(handler)
+    }
+}
+
+impl<'a> FromIterator<Box<dyn Handler + 'a>> for Chain<'a> {
+    fn from_iter<I: IntoIterator<Item = Box<dyn Handler + 'a>>>(iter: I) -> Self {
+        Chain {
+            handlers: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<'a> From<Chain<'a>> for Box<dyn Handler + 'a> {
+    fn from(handler: Chain<'a>) -> Self {
+        Box::new(handler)
+    }
+}
+
+/// A default implementation of the `Handler` trait.
+///
+/// This struct contains a single `value` that will be returned for any request,
+/// regardless of the provided key.
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{DefaultHandler, Handler};
+///
+/// // Create a new DefaultHandler for a specific value
+/// let handler = DefaultHandler::new("some_value");
+///
+/// // Add it to the end of a chain as a fallback handler
+/// //let handler = Chain::new().with(some_other_handler).with(handler);
+///
+/// // Handle a configuration request
+/// let value = handler.handle_request("some_key");
+/// ```
+pub struct DefaultHandler {
+    value: String,
+}
+
+impl DefaultHandler {
+    /// Creates a new `DefaultHandler` with the specified value.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to be returned for any request.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::DefaultHandler;
+    ///
+    /// let handler = DefaultHandler::new("some_value");
+    /// ```
+    #[allow(dead_code)]
+    pub fn new(value: &str) -> Self {
+        DefaultHandler {
+            value: String::from(value),
         }
+    }
+}
+
+impl Handler for DefaultHandler {
+    /// Always returns the stored value, regardless of the key.
+    ///
+    /// This implementation ignores the provided key and always returns the
+    /// value stored in the `DefaultHandler`.
+    fn handle_request(&self, _key: &str) -> Option<String> {
+        Some(self.value.clone())
+    }
+
+    fn handle_request_with_source(&self, _key: &str) -> Option<(String, Source)> {
+        Some((self.value.clone(), Source::Default))
+    }
+}
+
+#[allow(clippy::from_over_into)]
+impl<'a> Into<Box<dyn Handler + 'a>> for DefaultHandler {
+    fn into(self) -> Box<dyn Handler + 'a> {
+        Box::new(self)
+    }
+}
+
+#[cfg(feature = "clap")]
+pub mod internal_clap {
+    use super::*;
+    use clap::parser::ValueSource;
+    use clap::ArgMatches;
+    /// A handler for managing command-line arguments.
+    ///
+    /// This struct is responsible for handling command-line arguments passed to the application.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::{ArgHandler, Handler};
+    ///
+    /// // Create a simple `clap` command
+    /// let args = clap::Command::new("myapp")
+    ///     .arg(clap::Arg::new("example").long("example"))
+    ///     .get_matches();
+    ///
+    /// // Create a new ArgHandler for a `clap::ArgMatches`
+    /// let handler = ArgHandler::new(&args);
+    ///
+    /// // Handle a configuration request matching the `clap::Arg` name
+    /// let value = handler.handle_request("example");
+    /// ```
+    pub struct ArgHandler<'a> {
+        /// Parsed command-line arguments, either borrowed or owned by the handler.
+        args: Cow<'a, ArgMatches>,
+    }
 
+    impl<'a> ArgHandler<'a> {
+        /// Creates a new `ArgHandler` with the specified arguments.
+        ///
+        /// # Arguments
+        ///
+        /// * `args` - The parsed command-line arguments.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// use cor_args::ArgHandler;
+        ///
+        /// let args = clap::Command::new("myapp")
+        ///     .arg(clap::Arg::new("config").long("some-option"))
+        ///     .get_matches();
+        ///
+        /// let handler = ArgHandler::new(&args);
+        /// ```
         #[allow(dead_code)]
-        pub fn next(mut self, handler: Box<dyn Handler>) -> Self {
-            self.next = Some(handler);
-            self
+        pub fn new(args: &'a ArgMatches) -> Self {
+            ArgHandler {
+                args: Cow::Borrowed(args),
+            }
         }
+    }
 
-        /// Recursively searches for a key within the parsed Config structure.
+    impl ArgHandler<'static> {
+        /// Creates a new `ArgHandler` which takes ownership of the specified arguments.
+        ///
+        /// Unlike [`ArgHandler::new`], the handler does not borrow the arguments, so it can be
+        /// used in a [`SharedChain`] or returned from the function which parsed them.
         ///
         /// # Arguments
         ///
-        /// * `config_value` - The current Config value being inspected.
-        /// * `key` - The key for which the value needs to be retrieved.
+        /// * `args` - The parsed command-line arguments.
         ///
-        /// # Returns
+        /// # Examples
         ///
-        /// If found, returns an `Option` wrapping a `String` value associated with the key.
-        /// Otherwise, returns `None`.
-        pub fn find_key_recursive(config_value: &config::Value, key: &str) -> Option<String> {
-            match &config_value.kind {
-                config::ValueKind::Table(map) => {
-                    if let Some(value) = map.get(key) {
-                        match &value.kind {
-                            config::ValueKind::String(value) => {
-                                return Some(value.as_str().to_string())
-                            }
-                            _ => return Some(value.to_string()),
-                        }
-                    }
-                    for (_, value) in map.iter() {
-                        if let Some(found) = Self::find_key_recursive(value, key) {
-                            return Some(found);
-                        }
-                    }
-                }
-                config::ValueKind::Array(arr) => {
-                    for value in arr.iter() {
-                        if let Some(found) = Self::find_key_recursive(value, key) {
-                            return Some(found);
-                        }
-                    }
-                }
-                _ => {}
+        /// ```
+        /// use cor_args::ArgHandler;
+        ///
+        /// let args = clap::Command::new("myapp")
+        ///     .arg(clap::Arg::new("config").long("some-option"))
+        ///     .get_matches();
+        ///
+        /// let handler = ArgHandler::owned(args);
+        /// ```
+        #[allow(dead_code)]
+        pub fn owned(args: ArgMatches) -> Self {
+            ArgHandler {
+                args: Cow::Owned(args),
             }
-            None
         }
     }
 
-    impl Handler for ConfigHandler {
-        /// Handle a configuration request and return the value associated with the provided key.
+    impl<'a> ArgHandler<'a> {
+        /// Retrieves the value of a flag, such as an argument with `ArgAction::SetTrue`.
         ///
-        /// This method attempts to read the configuration file and retrieve the value associated
-        /// with the given key. If the key is not found, it may delegate the request to a fallback
-        /// handler if one is defined.
+        /// The implicit default of a flag which was not passed is ignored, so that the request
+        /// is passed on to the next handler rather than answered with `false`.
+        fn flag(&self, key: &str) -> Option<String> {
+            let value = self.args.try_get_one::<bool>(key).ok().flatten()?;
+            let passed = self.args.value_source(key) != Some(ValueSource::DefaultValue);
+            passed.then(|| value.to_string())
+        }
+    }
+
+    impl<'a> Handler for ArgHandler<'a> {
+        /// Retrieves a value for the specified key from the command-line arguments.
         ///
-        /// # Parameters
+        /// # Arguments
         ///
-        /// - `key`: A string representing the configuration key to retrieve.
+        /// * `key` - The key for which the value needs to be retrieved.
         ///
         /// # Returns
         ///
         /// An `Option` containing the value associated with the key, or `None` if the key is not found.
         fn handle_request(&self, key: &str) -> Option<String> {
-            if let Ok(parsed_config) = self.config.clone().try_deserialize::<config::Value>() {
-                if let Some(value) = Self::find_key_recursive(&parsed_config, key) {
-                    return Some(value);
-                }
-            }
-            if let Some(next_handler) = &self.next {
-                return next_handler.handle_request(key);
+            if let Ok(Some(value)) = self.args.try_get_one::<String>(key) {
+                return Some(value.clone());
             }
-            None
+            self.flag(key)
         }
-    }
 
-    impl Into<Box<dyn Handler>> for ConfigHandler {
-        fn into(self) -> Box<dyn Handler> {
-            Box::new(self)
+        /// Retrieves a value for the specified key, reporting the [`Source::Arg`] it came from.
+        fn handle_request_with_source(&self, key: &str) -> Option<(String, Source)> {
+            let source = Source::Arg {
+                id: key.to_string(),
+            };
+            self.handle_request(key).map(|value| (value, source))
         }
-    }
 
-    impl From<Result<config::Config, config::ConfigError>> for ConfigHandler {
-        fn from(value: Result<config::Config, config::ConfigError>) -> Self {
-            if let Ok(config) = value {
-                ConfigHandler::new(Box::new(config))
-            } else {
-                panic!("Failed to convert into a Config")
+        /// Retrieves all values for the specified key from the command-line arguments.
+        ///
+        /// This supports arguments which accept multiple values, e.g. `--include a --include b`.
+        fn handle_request_all(&self, key: &str) -> Option<Vec<String>> {
+            if let Ok(Some(values)) = self.args.try_get_many::<String>(key) {
+                return Some(values.cloned().collect());
             }
+            self.flag(key).map(|value| vec![value])
         }
-    }
 
-    impl From<config::Config> for ConfigHandler {
-        fn from(value: config::Config) -> Self {
-            ConfigHandler::new(Box::new(value))
+        /// Enumerates the ids of the arguments which have a value.
+        fn keys(&self) -> Option<Vec<String>> {
+            let keys = self
+                .args
+                .ids()
+                .filter(|id| match self.args.try_get_one::<bool>(id.as_str()) {
+                    Ok(Some(_)) => self.flag(id.as_str()).is_some(),
+                    _ => matches!(self.args.try_get_raw(id.as_str()), Ok(Some(_))),
+                })
+                .map(|id| id.to_string())
+                .collect();
+            Some(keys)
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
+    #[allow(clippy::from_over_into)]
+    impl<'a> Into<Box<dyn Handler + 'a>> for ArgHandler<'a> {
+        fn into(self) -> Box<dyn Handler + 'a> {
+            Box::new(self)
+        }
+    }
+
+    /// A settings struct whose command-line arguments, environment variables, file keys and
+    /// defaults are described in one place.
+    ///
+    /// This trait is usually implemented with `#[derive(CorArgs)]`, which is available with the
+    /// `derive` feature. Each field of the struct is configured with a `#[cor(...)]` attribute:
+    ///
+    /// * `long` / `long = "name"` - Accept the field as `--name`, defaulting to the field name
+    ///   with `_` replaced by `-`.
+    /// * `short = 'c'` - Accept the field as `-c`.
+    /// * `env` / `env = "NAME"` - Read the field from the environment variable `NAME`, defaulting
+    ///   to the upper case field name.
+    /// * `file_key = "key"` - Read the field from `key` in the configuration file, defaulting to
+    ///   the field name. Nested keys are separated by dots, e.g. `server.port`.
+    /// * `default = "value"` - Fall back to `value` if no other source has the field.
+    ///
+    /// The field's doc comment is used as the argument's help text. Fields of type `Option<T>` are
+    /// optional, all other fields must be found in one of the sources. Field types are parsed
+    /// using [`FromStr`]. Fields of type `bool` are flags, which are set to `true` by passing
+    /// them without a value, e.g. `--verbose`, and are `false` if no source has a value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::CorArgs;
+    ///
+    /// #[derive(CorArgs)]
+    /// struct AppConfig {
+    ///     /// The port to listen on.
+    ///     #[cor(long = "port", env = "MYAPP_PORT", file_key = "server.port", default = "8080")]
+    ///     port: u16,
+    ///     #[cor(long)]
+    ///     log_file: Option<String>,
+    /// }
+    ///
+    /// let matches = clap::Command::new("myapp")
+    ///     .args(AppConfig::args())
+    ///     .get_matches_from(["myapp", "--log-file", "myapp.log"]);
+    ///
+    /// let config = AppConfig::from_matches(&matches, None).unwrap();
+    /// assert_eq!(config.port, 8080);
+    /// assert_eq!(config.log_file, Some("myapp.log".to_string()));
+    /// ```
+    #[cfg(feature = "derive")]
+    pub trait CorArgs: Sized {
+        /// Returns the `clap::Arg` definitions of the fields with a `long` or `short` name.
+        fn args() -> Vec<clap::Arg>;
+
+        /// Builds the chain which resolves the fields of the struct by their field names.
+        ///
+        /// The chain queries, in order, the command-line arguments, the environment variables,
+        /// the JSON `config_file` if there is one, and the defaults.
+        ///
+        /// # Arguments
+        ///
+        /// * `matches` - The command-line arguments parsed with the definitions from
+        ///   [`CorArgs::args`].
+        /// * `config_file` - The path of an optional JSON configuration file.
+        fn chain<'a>(matches: &'a ArgMatches, config_file: Option<PathBuf>) -> Chain<'a>;
+
+        /// Constructs the struct by resolving each field by its field name.
+        ///
+        /// # Returns
+        ///
+        /// The struct, an [`Error::MissingKey`] if a required field has no value, or an
+        /// [`Error::InvalidValue`] if a value could not be parsed.
+        fn from_handler<H: Handler + ?Sized>(handler: &H) -> Result<Self, Error>;
+
+        /// Constructs the struct from the chain built by [`CorArgs::chain`].
+        fn from_matches(matches: &ArgMatches, config_file: Option<PathBuf>) -> Result<Self, Error> {
+            Self::from_handler(&Self::chain(matches, config_file))
+        }
+    }
+}
+
+/// A handler for retrieving values from environment variables.
+///
+/// This struct is responsible for handling requests by checking for the existence of
+/// an environment variable corresponding to the provided key.
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{EnvHandler, Handler};
+///
+/// // Create a new EnvHandler specifying a prefix for environment variables
+/// let handler = EnvHandler::new().prefix("MYAPP_");
+///
+/// // Handle a configuration request matching `MYAPP_some_key`
+/// let value = handler.handle_request("some_key");
+/// ```
+pub struct EnvHandler<'a> {
+    /// A prefix to prepend to the key passed to `handle_request()`.
+    prefix: Option<Cow<'a, str>>,
+    /// A delimiter used by `handle_request_all()` to split a value into multiple values.
+    delimiter: Option<Cow<'a, str>>,
+}
+
+impl<'a> EnvHandler<'a> {
+    /// Creates a new `EnvHandler`.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - An optional prefix to which requests will prepend when `handle_request()` is executed.` If `None`, an empty string is assigned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::EnvHandler;
+    ///
+    /// let handler = EnvHandler::new();
+    /// ```
+    #[allow(dead_code)]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        EnvHandler {
+            prefix: None,
+            delimiter: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn prefix<S>(mut self, prefix: S) -> Self
+    where
+        S: Into<Cow<'a, str>>,
+    {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Sets the delimiter used to split a variable's value by `handle_request_all()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `delimiter` - The string separating values, e.g. `","` or `":"`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::EnvHandler;
+    ///
+    /// let handler = EnvHandler::new().delimiter(":");
+    /// ```
+    #[allow(dead_code)]
+    pub fn delimiter<S>(mut self, delimiter: S) -> Self
+    where
+        S: Into<Cow<'a, str>>,
+    {
+        self.delimiter = Some(delimiter.into());
+        self
+    }
+
+    /// Returns the name of the environment variable queried for `key`.
+    fn var_name(&self, key: &str) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("{prefix}{key}"),
+            None => key.to_string(),
+        }
+    }
+}
+
+impl<'a> Handler for EnvHandler<'a> {
+    /// Retrieves a value for the specified key from the environment variables.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key for which the value needs to be retrieved from environment variables.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the value associated with the key, or `None` if the key is not found.
+    fn handle_request(&self, key: &str) -> Option<String> {
+        env::var(self.var_name(key)).ok()
+    }
+
+    /// Retrieves a value for the specified key, reporting the [`Source::Env`] it came from.
+    fn handle_request_with_source(&self, key: &str) -> Option<(String, Source)> {
+        let var = self.var_name(key);
+        match env::var(&var) {
+            Ok(value) => Some((value, Source::Env { var })),
+            Err(_) => None,
+        }
+    }
+
+    /// Retrieves all values for the specified key from the environment variables.
+    ///
+    /// If a delimiter is set, the variable's value is split on it. Otherwise the whole value is
+    /// returned as the only element.
+    fn handle_request_all(&self, key: &str) -> Option<Vec<String>> {
+        let value = env::var(self.var_name(key)).ok()?;
+        match &self.delimiter {
+            Some(delimiter) => Some(value.split(delimiter.as_ref()).map(String::from).collect()),
+            None => Some(vec![value]),
+        }
+    }
+
+    /// Enumerates the environment variables, with the prefix removed if one is set.
+    ///
+    /// If a prefix is set, only variables starting with the prefix are included.
+    fn keys(&self) -> Option<Vec<String>> {
+        let prefix = self.prefix.as_deref().unwrap_or("");
+        let keys = env::vars_os()
+            .filter_map(|(var, _)| var.into_string().ok())
+            .filter_map(|var| var.strip_prefix(prefix).map(String::from))
+            .filter(|key| !key.is_empty())
+            .collect();
+        Some(keys)
+    }
+}
+
+#[allow(clippy::from_over_into)]
+impl<'a> Into<Box<dyn Handler + 'a>> for EnvHandler<'a> {
+    fn into(self) -> Box<dyn Handler + 'a> {
+        Box::new(self)
+    }
+}
+
+/// A handler for retrieving values from a file.
+///
+/// This struct is responsible for handling requests by checking for values within a specified file.
+///
+/// By default the whole content of the file is returned as is. The content can be post-processed
+/// with [`trim`](FileHandler::trim), [`first_line`](FileHandler::first_line) and
+/// [`base64`](FileHandler::base64), and the amount of data read can be limited with
+/// [`max_size`](FileHandler::max_size).
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{FileHandler, Handler};
+///
+/// // Create a new FileHandler specifying a path to a file.
+/// let handler = FileHandler::new("/path/to/file");
+///
+/// // Handle a configuration request returning contents of `/path/to/file`
+/// let value = handler.handle_request("");
+/// ```
+pub struct FileHandler {
+    /// Path to the file from which values are to be retrieved.
+    file_path: PathBuf,
+    /// Whether leading and trailing whitespace is removed from the content.
+    trim: bool,
+    /// Whether only the first line of the content is returned.
+    first_line: bool,
+    /// The maximum number of bytes read from the file.
+    max_size: Option<u64>,
+    /// Whether the content is returned base64 encoded.
+    base64: bool,
+    /// How a chain treats a failure to read the file.
+    error_policy: ErrorPolicy,
+}
+
+impl FileHandler {
+    /// Creates a new `FileHandler` with the specified file path.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - The path to the file from which values are to be retrieved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::FileHandler;
+    ///
+    /// let handler = FileHandler::new("/path/to/file");
+    /// ```
+    #[allow(dead_code)]
+    pub fn new<P>(file_path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        FileHandler {
+            file_path: file_path.into(),
+            trim: false,
+            first_line: false,
+            max_size: None,
+            base64: false,
+            error_policy: ErrorPolicy::default(),
+        }
+    }
+
+    /// Sets whether leading and trailing whitespace, including the trailing newline, is removed
+    /// from the content.
+    ///
+    /// # Arguments
+    ///
+    /// * `trim` - `true` to trim the content. Defaults to `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::FileHandler;
+    ///
+    /// let handler = FileHandler::new("verbosity.txt").trim(true);
+    /// ```
+    #[allow(dead_code)]
+    pub fn trim(mut self, trim: bool) -> Self {
+        self.trim = trim;
+        self
+    }
+
+    /// Sets whether only the first line of the content is returned, without its line ending.
+    ///
+    /// If [`trim`](FileHandler::trim) is set as well, the first line is trimmed.
+    ///
+    /// # Arguments
+    ///
+    /// * `first_line` - `true` to return only the first line. Defaults to `false`.
+    #[allow(dead_code)]
+    pub fn first_line(mut self, first_line: bool) -> Self {
+        self.first_line = first_line;
+        self
+    }
+
+    /// Sets the maximum number of bytes read from the file.
+    ///
+    /// A file which is larger is reported as an [`Error::Io`] instead of being read, so that
+    /// pointing the handler at a file such as `/dev/zero` cannot exhaust memory or hang.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_size` - The maximum size of the file in bytes. Unlimited by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::{FileHandler, Handler};
+    ///
+    /// let handler = FileHandler::new("/dev/zero").max_size(1024);
+    /// assert!(handler.try_handle_request("").is_err());
+    /// ```
+    #[allow(dead_code)]
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Sets whether the content is returned base64 encoded.
+    ///
+    /// This allows files with binary content, which is not valid UTF-8, to be read. The content
+    /// is encoded as is, so [`trim`](FileHandler::trim) and
+    /// [`first_line`](FileHandler::first_line) do not apply.
+    ///
+    /// # Arguments
+    ///
+    /// * `base64` - `true` to encode the content using the standard base64 alphabet with
+    ///   padding. Defaults to `false`.
+    #[allow(dead_code)]
+    pub fn base64(mut self, base64: bool) -> Self {
+        self.base64 = base64;
+        self
+    }
+
+    /// Sets how a chain treats a failure to read the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `error_policy` - The policy applied to read errors. Defaults to [`ErrorPolicy::Fail`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::{ErrorPolicy, FileHandler};
+    ///
+    /// let handler = FileHandler::new("/path/to/file").on_error(ErrorPolicy::FallThrough);
+    /// ```
+    #[allow(dead_code)]
+    pub fn on_error(mut self, error_policy: ErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// Reads the raw content of the file, up to the maximum size.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(None)` if the file does not exist, `Ok(Some(content))` if it was read successfully,
+    /// or an [`Error::Io`] if it exists but could not be read or exceeds the maximum size.
+    fn read_bytes(&self) -> Result<Option<Vec<u8>>, Error> {
+        let file = match File::open(&self.file_path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(Error::io(&self.file_path, e)),
+        };
+        let limit = self
+            .max_size
+            .map_or(u64::MAX, |max_size| max_size.saturating_add(1));
+        let mut content = Vec::new();
+        file.take(limit)
+            .read_to_end(&mut content)
+            .map_err(|e| Error::io(&self.file_path, e))?;
+        if let Some(max_size) = self.max_size.filter(|&max| content.len() as u64 > max) {
+            let e = io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("file exceeds the maximum size of {max_size} bytes"),
+            );
+            return Err(Error::io(&self.file_path, e));
+        }
+        Ok(Some(content))
+    }
+
+    /// Reads the content of the file as text.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(None)` if the file does not exist, `Ok(Some(content))` if it was read successfully,
+    /// or an [`Error::Io`] if it exists but could not be read or is not valid UTF-8.
+    fn read(&self) -> Result<Option<String>, Error> {
+        match self.read_bytes()? {
+            Some(content) => String::from_utf8(content).map(Some).map_err(|e| {
+                Error::io(
+                    &self.file_path,
+                    io::Error::new(io::ErrorKind::InvalidData, e.utf8_error()),
+                )
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the content of the file and applies the configured post-processing.
+    fn value(&self) -> Result<Option<String>, Error> {
+        if self.base64 {
+            return Ok(self
+                .read_bytes()?
+                .map(|content| BASE64_STANDARD.encode(content)));
+        }
+        Ok(self.read()?.map(|content| {
+            let content = if self.first_line {
+                content.lines().next().unwrap_or_default()
+            } else {
+                content.as_str()
+            };
+            let content = if self.trim { content.trim() } else { content };
+            content.to_string()
+        }))
+    }
+}
+
+impl Handler for FileHandler {
+    /// Retrieves content from the specified file.
+    ///
+    /// This implementation attempts to read content from the file specified by `file_path`.
+    /// If reading fails, it returns `None`.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key for which the value needs to be retrieved. (Note: The `key` is currently not used.)
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the contents of the file, or `None` if the file could not be read.
+    fn handle_request(&self, _key: &str) -> Option<String> {
+        self.value().ok().flatten()
+    }
+
+    /// Retrieves content from the specified file, reporting read failures.
+    ///
+    /// A file that does not exist is treated as a missing value. Any other failure to read the
+    /// file is returned as an [`Error::Io`].
+    fn try_handle_request(&self, _key: &str) -> Result<Option<String>, Error> {
+        self.value()
+    }
+
+    /// Retrieves content from the specified file, reporting the [`Source::File`] it came from.
+    fn handle_request_with_source(&self, key: &str) -> Option<(String, Source)> {
+        self.try_handle_request_with_source(key).ok().flatten()
+    }
+
+    /// Retrieves content from the specified file, reporting the [`Source::File`] it came from
+    /// and read failures.
+    fn try_handle_request_with_source(
+        &self,
+        _key: &str,
+    ) -> Result<Option<(String, Source)>, Error> {
+        let source = Source::File {
+            path: self.file_path.clone(),
+        };
+        Ok(self.value()?.map(|content| (content, source)))
+    }
+
+    /// Retrieves content from the specified file, reporting read failures.
+    fn try_handle_request_all(&self, _key: &str) -> Result<Option<Vec<String>>, Error> {
+        Ok(self.value()?.map(|content| vec![content]))
+    }
+
+    fn error_policy(&self) -> ErrorPolicy {
+        self.error_policy
+    }
+}
+
+#[allow(clippy::from_over_into)]
+impl<'a> Into<Box<dyn Handler + 'a>> for FileHandler {
+    fn into(self) -> Box<dyn Handler + 'a> {
+        Box::new(self)
+    }
+}
+
+/// A function mapping a key to the name of the file containing its value.
+type FileNameFn = dyn Fn(&str) -> String + Send + Sync;
+
+/// A handler for retrieving values from a directory containing one file per key.
+///
+/// A request for `key` returns the content of the file `<dir>/<key>`. This is how Docker secrets
+/// (`/run/secrets`), Kubernetes ConfigMaps and Secrets mounted as volumes, and systemd
+/// credentials (`$CREDENTIALS_DIRECTORY`) are exposed.
+///
+/// Keys which could refer to a file outside of the directory, i.e. keys which are empty or
+/// contain a path separator or `..`, are refused and treated as missing.
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{DirectoryHandler, Handler};
+///
+/// // Create a new DirectoryHandler for the Docker secrets directory
+/// let handler = DirectoryHandler::new("/run/secrets").trim_newline(true);
+///
+/// // Handle a configuration request matching the file `/run/secrets/db_password`
+/// let value = handler.handle_request("db_password");
+/// ```
+pub struct DirectoryHandler {
+    /// Path to the directory containing one file per key.
+    dir_path: PathBuf,
+    /// Maps a key to the name of the file containing its value.
+    file_name: Option<Box<FileNameFn>>,
+    /// Whether a trailing newline is removed from the content of the files.
+    trim_newline: bool,
+    /// How a chain treats a failure to read a file.
+    error_policy: ErrorPolicy,
+}
+
+impl DirectoryHandler {
+    /// Creates a new `DirectoryHandler` with the specified directory path.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir_path` - The path to the directory containing one file per key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::DirectoryHandler;
+    ///
+    /// let handler = DirectoryHandler::new("/run/secrets");
+    /// ```
+    #[allow(dead_code)]
+    pub fn new<P>(dir_path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        DirectoryHandler {
+            dir_path: dir_path.into(),
+            file_name: None,
+            trim_newline: false,
+            error_policy: ErrorPolicy::default(),
+        }
+    }
+
+    /// Sets a function which maps a key to the name of the file containing its value.
+    ///
+    /// By default the key itself is used as the file name. The mapped file name is subject to
+    /// the same checks as a key.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_name` - A function returning the file name for a key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::DirectoryHandler;
+    ///
+    /// // Map `db.password` to the file `DB_PASSWORD`
+    /// let handler = DirectoryHandler::new("/run/secrets")
+    ///     .file_name(|key| key.replace('.', "_").to_uppercase());
+    /// ```
+    #[allow(dead_code)]
+    pub fn file_name<F>(mut self, file_name: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.file_name = Some(Box::new(file_name));
+        self
+    }
+
+    /// Sets whether a trailing newline is removed from the content of the files.
+    ///
+    /// # Arguments
+    ///
+    /// * `trim_newline` - `true` to remove a trailing `\n` or `\r\n`. Defaults to `false`.
+    #[allow(dead_code)]
+    pub fn trim_newline(mut self, trim_newline: bool) -> Self {
+        self.trim_newline = trim_newline;
+        self
+    }
+
+    /// Sets how a chain treats a failure to read a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `error_policy` - The policy applied to read errors. Defaults to [`ErrorPolicy::Fail`].
+    #[allow(dead_code)]
+    pub fn on_error(mut self, error_policy: ErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// Returns the path of the file containing the value of `key`.
+    ///
+    /// # Returns
+    ///
+    /// The path, or `None` if the key, or the file name it maps to, could refer to a file outside
+    /// of the directory.
+    fn file_path(&self, key: &str) -> Option<PathBuf> {
+        let file_name = match &self.file_name {
+            Some(file_name) => file_name(key),
+            None => key.to_string(),
+        };
+        let refused = file_name.is_empty()
+            || file_name.contains(['/', '\\', '\0'])
+            || file_name.contains("..");
+        if refused {
+            return None;
+        }
+        Some(self.dir_path.join(file_name))
+    }
+
+    /// Reads the content of the file containing the value of `key`.
+    fn read(&self, key: &str) -> Result<Option<(String, PathBuf)>, Error> {
+        let Some(file_path) = self.file_path(key) else {
+            return Ok(None);
+        };
+        let content = FileHandler::new(&file_path).read()?.map(|mut content| {
+            if self.trim_newline && content.ends_with('\n') {
+                content.pop();
+                if content.ends_with('\r') {
+                    content.pop();
+                }
+            }
+            content
+        });
+        Ok(content.map(|content| (content, file_path)))
+    }
+}
+
+impl Handler for DirectoryHandler {
+    /// Retrieves the content of the file named after the specified key.
+    fn handle_request(&self, key: &str) -> Option<String> {
+        self.try_handle_request(key).ok().flatten()
+    }
+
+    /// Retrieves the content of the file named after the specified key, reporting read failures.
+    ///
+    /// A file that does not exist, or a refused key, is treated as a missing value. Any other
+    /// failure to read the file is returned as an [`Error::Io`].
+    fn try_handle_request(&self, key: &str) -> Result<Option<String>, Error> {
+        Ok(self.read(key)?.map(|(content, _)| content))
+    }
+
+    /// Retrieves the content of the file named after the specified key, reporting the
+    /// [`Source::File`] it came from.
+    fn handle_request_with_source(&self, key: &str) -> Option<(String, Source)> {
+        self.try_handle_request_with_source(key).ok().flatten()
+    }
+
+    /// Retrieves the content of the file named after the specified key, reporting the
+    /// [`Source::File`] it came from and read failures.
+    fn try_handle_request_with_source(&self, key: &str) -> Result<Option<(String, Source)>, Error> {
+        Ok(self
+            .read(key)?
+            .map(|(content, path)| (content, Source::File { path })))
+    }
+
+    /// Retrieves the content of the file named after the specified key, reporting read failures.
+    fn try_handle_request_all(&self, key: &str) -> Result<Option<Vec<String>>, Error> {
+        Ok(self.read(key)?.map(|(content, _)| vec![content]))
+    }
+
+    /// Enumerates the names of the files in the directory.
+    ///
+    /// Hidden files, such as the `..data` link of a Kubernetes volume, and directories are
+    /// skipped. Returns `None` if a [file name mapping](DirectoryHandler::file_name) is set,
+    /// since file names cannot be mapped back to keys.
+    fn keys(&self) -> Option<Vec<String>> {
+        if self.file_name.is_some() {
+            return None;
+        }
+        let Ok(entries) = fs::read_dir(&self.dir_path) else {
+            return Some(Vec::new());
+        };
+        let mut keys: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| !name.starts_with('.'))
+            .collect();
+        keys.sort();
+        Some(keys)
+    }
+
+    fn error_policy(&self) -> ErrorPolicy {
+        self.error_policy
+    }
+}
+
+impl<'a> From<DirectoryHandler> for Box<dyn Handler + 'a> {
+    fn from(handler: DirectoryHandler) -> Self {
+        Box::new(handler)
+    }
+}
+
+/// A handler for retrieving values from a specified JSON file.
+///
+/// This struct is responsible for handling requests by reading content from the file
+/// specified in the underlying `FileHandler`, and then searching for a specific key
+/// within the parsed JSON structure.
+///
+/// Keys are resolved as follows:
+///
+/// * A key starting with `/` is a JSON Pointer as defined in RFC 6901, e.g. `/servers/0/port`.
+/// * A key containing `.` is a dotted path from the root, e.g. `db.host` or `servers.0.port`.
+///   Array elements are addressed by their index. If the path does not exist, the key is
+///   searched for literally, so keys such as `"db.host"` can still be found.
+/// * Any other key is searched for by name, first at the root and then at any depth. By default
+///   the first match is returned. In [strict](JSONFileHandler::strict) mode a key which is not
+///   at the root and occurs more than once is reported as an [`Error::AmbiguousKey`].
+///
+/// The file is parsed once, on the first request, into an index of all of its keys, which is
+/// reused by later requests. To pick up changes to the file, enable
+/// [revalidation](JSONFileHandler::revalidate).
+///
+/// ```
+/// use cor_args::{JSONFileHandler, Handler};
+///
+/// // Create a new JSONFileHandler specifying a path to a file.
+/// let handler = JSONFileHandler::new("file.json");
+///
+/// // Handle a configuration request matching a `"some_key"` within `file.json`
+/// let value = handler.handle_request("some_key");
+/// ```
+pub struct JSONFileHandler {
+    /// The JSON file, together with the cached index of its keys.
+    tree: TreeFile,
+}
+
+impl JSONFileHandler {
+    /// Creates a new `JSONFileHandler` with the specified file path.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - The path to the JSON file from which values are to be retrieved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::JSONFileHandler;
+    ///
+    /// let handler = JSONFileHandler::new("file.json");
+    /// ```
+    #[allow(dead_code)]
+    pub fn new<P>(file_path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        JSONFileHandler {
+            tree: TreeFile::new(file_path, "JSON"),
+        }
+    }
+
+    /// Searches for a key within the parsed JSON structure.
+    ///
+    /// The key is resolved the same way as by [`JSONFileHandler::handle_request`], so a bare
+    /// name which occurs more than once finds the occurrence nearest to the root.
+    ///
+    /// # Arguments
+    ///
+    /// * `json_value` - The current JSON value being inspected.
+    /// * `key` - The key for which the value needs to be retrieved.
+    ///
+    /// # Returns
+    ///
+    /// If found, returns an `Option` wrapping a `String` value associated with the key.
+    /// Otherwise, returns `None`.
+    pub fn find_key_recursive(json_value: &Value, key: &str) -> Option<String> {
+        TreeIndex::new(Some(json_value.clone()), None)
+            .find_value(key)
+            .map(|(value, _)| tree::value_to_string(value))
+    }
+
+    /// Parses the content of the file as JSON.
+    fn parse_content(&self, content: &str) -> Result<Value, String> {
+        serde_json::from_str(content).map_err(|e| e.to_string())
+    }
+}
+
+tree_file_handler!(JSONFileHandler, "file.json");
+
+#[allow(clippy::from_over_into)]
+impl<'a> Into<Box<dyn Handler + 'a>> for JSONFileHandler {
+    fn into(self) -> Box<dyn Handler + 'a> {
+        Box::new(self)
+    }
+}
+
+#[cfg(feature = "config")]
+pub mod internal_config {
+    use super::*;
+    use config::Config;
+    use std::sync::OnceLock;
+    /// A configuration file handler for reading key-value pairs from a file.
+    ///
+    /// The `ConfigHandler` is used to read configuration data from a file and provide it
+    /// as key-value pairs.
+    ///
+    /// The `Config` is converted once, on the first request, into an index of its keys, which is
+    /// reused by later requests. Keys are resolved as described for [`JSONFileHandler`], and
+    /// values which are not strings are returned as JSON.
+    ///
+    /// A `Config` built by a `config::ConfigBuilder` is a snapshot which does not change when its
+    /// source files do, so unlike the file handlers, a `ConfigHandler` has nothing to revalidate.
+    /// Build a new `Config` and `ConfigHandler` to pick up changes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::{ConfigHandler, Handler};
+    ///
+    /// // Example YAML file
+    /// // ---
+    /// // test_obj:
+    /// //     some_key: "test_val"
+    ///
+    /// let config = config::Config::builder().build().unwrap();
+    /// //    .add_source(config::File::new("/path/to/file",
+    /// //        config::FileFormat::Yaml,
+    /// //    ))
+    /// //    .build()
+    /// //    .unwrap();
+    ///
+    /// // Create a new ConfigHandler for a specific config::Config instance
+    /// let handler = ConfigHandler::new(Box::new(config));
+    ///
+    /// // Handle a configuration request
+    /// let value = handler.handle_request("some_key");
+    /// ```
+    pub struct ConfigHandler {
+        /// The Config instance ultimately being queried.
+        config: Box<config::Config>,
+        /// The index of the deserialized Config, or the reason it could not be deserialized.
+        index: OnceLock<Result<TreeIndex, String>>,
+        /// How a chain treats a failure to deserialize the Config.
+        error_policy: ErrorPolicy,
+    }
+
+    impl ConfigHandler {
+        /// Create a new `ConfigHandler` for the specified file path.
+        ///
+        /// # Parameters
+        ///
+        /// - `config`: A `config::Config` reference.
+        ///
+        /// # Returns
+        ///
+        /// A new `ConfigHandler` instance.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// use cor_args::ConfigHandler;
+        ///
+        /// let handler = ConfigHandler::new(Box::new(config::Config::builder().build().unwrap()));
+        /// ```
+        #[allow(dead_code)]
+        pub fn new(config: Box<Config>) -> Self {
+            ConfigHandler {
+                config,
+                index: OnceLock::new(),
+                error_policy: ErrorPolicy::default(),
+            }
+        }
+
+        /// Sets how a chain treats a failure to deserialize the `Config`.
+        ///
+        /// # Arguments
+        ///
+        /// * `error_policy` - The policy applied to deserialize errors. Defaults to
+        ///   [`ErrorPolicy::Fail`].
+        #[allow(dead_code)]
+        pub fn on_error(mut self, error_policy: ErrorPolicy) -> Self {
+            self.error_policy = error_policy;
+            self
+        }
+
+        /// Returns the index of the `Config`, deserializing it if necessary.
+        ///
+        /// # Returns
+        ///
+        /// The index, or an [`Error::Deserialize`] if the `Config` could not be deserialized.
+        fn index(&self) -> Result<&TreeIndex, Error> {
+            let index = self.index.get_or_init(|| {
+                self.config
+                    .clone()
+                    .try_deserialize::<Value>()
+                    .map(|json| TreeIndex::new(Some(json), None))
+                    .map_err(|e| e.to_string())
+            });
+            index.as_ref().map_err(|reason| Error::Deserialize {
+                handler: "ConfigHandler".to_string(),
+                reason: reason.clone(),
+            })
+        }
+
+        /// Searches for a key within the parsed Config structure.
+        ///
+        /// The key is resolved the same way as by [`ConfigHandler::handle_request`], so a bare
+        /// name which occurs more than once finds the occurrence nearest to the root.
+        ///
+        /// # Arguments
+        ///
+        /// * `config_value` - The current Config value being inspected.
+        /// * `key` - The key for which the value needs to be retrieved.
+        ///
+        /// # Returns
+        ///
+        /// If found, returns an `Option` wrapping a `String` value associated with the key.
+        /// Otherwise, returns `None`.
+        pub fn find_key_recursive(config_value: &config::Value, key: &str) -> Option<String> {
+            let json = config_value.clone().try_deserialize::<Value>().ok()?;
+            TreeIndex::new(Some(json), None)
+                .find_value(key)
+                .map(|(value, _)| tree::value_to_string(value))
+        }
+    }
+
+    impl Handler for ConfigHandler {
+        /// Handle a configuration request and return the value associated with the provided key.
+        ///
+        /// This method attempts to read the configuration file and retrieve the value associated
+        /// with the given key.
+        ///
+        /// # Parameters
+        ///
+        /// - `key`: A string representing the configuration key to retrieve.
+        ///
+        /// # Returns
+        ///
+        /// An `Option` containing the value associated with the key, or `None` if the key is not found.
+        fn handle_request(&self, key: &str) -> Option<String> {
+            self.try_handle_request(key).ok().flatten()
+        }
+
+        /// Handle a configuration request, reporting a failure to deserialize the `Config`.
+        ///
+        /// # Returns
+        ///
+        /// `Ok(Some(value))` if the key is found, `Ok(None)` if it is not, or an
+        /// [`Error::Deserialize`] if the `Config` could not be deserialized.
+        fn try_handle_request(&self, key: &str) -> Result<Option<String>, Error> {
+            Ok(self
+                .index()?
+                .find_value(key)
+                .map(|(value, _)| tree::value_to_string(value)))
+        }
+
+        /// Handle a configuration request, reporting the [`Source::Config`] the value came from.
+        ///
+        /// The reported key is the full path at which the key was found within the configuration.
+        fn handle_request_with_source(&self, key: &str) -> Option<(String, Source)> {
+            self.try_handle_request_with_source(key).ok().flatten()
+        }
+
+        /// Handle a configuration request, reporting the [`Source::Config`] the value came from
+        /// and a failure to deserialize the `Config`.
+        fn try_handle_request_with_source(
+            &self,
+            key: &str,
+        ) -> Result<Option<(String, Source)>, Error> {
+            Ok(self
+                .index()?
+                .find_value(key)
+                .map(|(value, path)| (tree::value_to_string(value), Source::Config { key: path })))
+        }
+
+        /// Retrieves all values for the specified key from the configuration.
+        ///
+        /// If the key is associated with an array, each element is returned as a separate value.
+        /// Otherwise the value is returned as the only element.
+        fn handle_request_all(&self, key: &str) -> Option<Vec<String>> {
+            self.try_handle_request_all(key).ok().flatten()
+        }
+
+        /// Retrieves all values for the specified key from the configuration, reporting a
+        /// failure to deserialize the `Config`.
+        fn try_handle_request_all(&self, key: &str) -> Result<Option<Vec<String>>, Error> {
+            Ok(self.index()?.find_value(key).map(|(value, _)| match value {
+                Value::Array(arr) => arr.iter().map(tree::value_to_string).collect(),
+                _ => vec![tree::value_to_string(value)],
+            }))
+        }
+
+        /// Enumerates the names of all keys within the configuration, at any depth.
+        fn keys(&self) -> Option<Vec<String>> {
+            let keys = self
+                .index()
+                .map(|index| index.names().to_vec())
+                .unwrap_or_default();
+            Some(keys)
+        }
+
+        fn error_policy(&self) -> ErrorPolicy {
+            self.error_policy
+        }
+    }
+
+    #[allow(clippy::from_over_into)]
+    impl<'a> Into<Box<dyn Handler + 'a>> for ConfigHandler {
+        fn into(self) -> Box<dyn Handler + 'a> {
+            Box::new(self)
+        }
+    }
+
+    impl From<Result<config::Config, config::ConfigError>> for ConfigHandler {
+        fn from(value: Result<config::Config, config::ConfigError>) -> Self {
+            if let Ok(config) = value {
+                ConfigHandler::new(Box::new(config))
+            } else {
+                panic!("Failed to convert into a Config")
+            }
+        }
+    }
+
+    impl From<config::Config> for ConfigHandler {
+        fn from(value: config::Config) -> Self {
+            ConfigHandler::new(Box::new(value))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
     use std::io::Write;
     use tempfile::NamedTempFile;
 
-    use super::*;
+    use super::*;
+
+    #[cfg(feature = "clap")]
+    #[test]
+    fn test_clap_features_chain_of_responsibility() {
+        env::set_var("TEST_KEY", "EnvHandler");
+        let args = clap::Command::new("test_app")
+            .arg(clap::Arg::new("example").long("example"))
+            .get_matches_from(vec!["test_app", "--example", "ArgHandler"]);
+        let temp_dir = tempfile::tempdir().unwrap();
+        // Don't create the temporary file so the chain keeps going to the end for this test.
+        let raw_file = temp_dir.path().join("should-not-exist.txt");
+        let mut json_file = NamedTempFile::new().unwrap();
+        writeln!(json_file, r#"{{"test_key": "JSONFileHandler"}}"#).unwrap();
+
+        let handler = Chain::new()
+            .with(ArgHandler::new(&args))
+            .with(EnvHandler::new())
+            .with(FileHandler::new(raw_file.as_path().to_str().unwrap()))
+            .with(JSONFileHandler::new(json_file.path().to_str().unwrap()))
+            .with(DefaultHandler::new("DefaultHandler"));
+        let actual = handler.handle_request("");
+        assert_eq!(actual, Some("DefaultHandler".to_string()));
+    }
+
+    #[test]
+    fn test_default_features_chain_of_responsibility() {
+        env::set_var("TEST_KEY", "EnvHandler");
+        let temp_dir = tempfile::tempdir().unwrap();
+        // Don't create the temporary file so the chain keeps going to the end for this test.
+        let raw_file = temp_dir.path().join("should-not-exist.txt");
+        let mut json_file = NamedTempFile::new().unwrap();
+        writeln!(json_file, r#"{{"test_key": "JSONFileHandler"}}"#).unwrap();
+
+        let handler = Chain::new()
+            .with(EnvHandler::new())
+            .with(FileHandler::new(raw_file.as_path().to_str().unwrap()))
+            .with(JSONFileHandler::new(json_file.path().to_str().unwrap()))
+            .with(DefaultHandler::new("DefaultHandler"));
+        let actual = handler.handle_request("");
+        assert_eq!(actual, Some("DefaultHandler".to_string()));
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_config_features_chain_of_responsibility() {
+        env::set_var("UNUSED", "EnvHandler");
+        let mut temp_file = tempfile::Builder::new().suffix(".yml").tempfile().unwrap();
+        let expected = r#"
+        ---
+        test_obj:
+            test_key: "test_val"
+        "#;
+        writeln!(temp_file, "{}", unindent::unindent(expected)).unwrap();
+
+        let config = config::Config::builder()
+            .add_source(config::File::new(
+                temp_file.path().to_str().unwrap(),
+                config::FileFormat::Yaml,
+            ))
+            .build()
+            .unwrap();
+
+        let handler = Chain::new()
+            .with(EnvHandler::new())
+            .with(ConfigHandler::new(Box::new(config)));
+        let actual = handler.handle_request("test_key");
+        assert_eq!(actual, Some("test_val".to_string()));
+    }
+
+    #[test]
+    fn test_chain_reports_source_of_value() {
+        env::remove_var("SOURCE_TEST_verbosity"); // Ensure the variable is not set
+        let temp_dir = tempfile::tempdir().unwrap();
+        let raw_file = temp_dir.path().join("should-not-exist.txt");
+        let mut json_file = NamedTempFile::new().unwrap();
+        writeln!(json_file, r#"{{"logging": {{"verbosity": "debug"}}}}"#).unwrap();
+
+        let handler = Chain::new()
+            .with(EnvHandler::new().prefix("SOURCE_TEST_"))
+            .with(FileHandler::new(raw_file))
+            .with(JSONFileHandler::new(json_file.path()));
+        let actual = handler.handle_request_with_source("verbosity");
+        let expected = Source::FileKey {
+            format: "JSON",
+            path: json_file.path().to_path_buf(),
+            key: "logging.verbosity".to_string(),
+        };
+        assert_eq!(actual, Some(("debug".to_string(), expected)));
+    }
+
+    #[test]
+    fn test_chain_enumerates_union_of_keys() {
+        env::set_var("KEYS_TEST_port", "8080");
+        let mut json_file = NamedTempFile::new().unwrap();
+        writeln!(json_file, r#"{{"port": 80, "host": "localhost"}}"#).unwrap();
+
+        let handler = Chain::new()
+            .with(EnvHandler::new().prefix("KEYS_TEST_"))
+            .with(JSONFileHandler::new(json_file.path()))
+            .with(DefaultHandler::new("DefaultHandler"));
+        let actual = handler.keys();
+        assert_eq!(actual, Some(vec!["port".to_string(), "host".to_string()]));
+    }
+
+    mod handler_get {
+        use super::*;
+
+        #[test]
+        fn test_parses_value() {
+            let handler = DefaultHandler::new("8080");
+            let actual = handler.get::<u16>("port").unwrap();
+            assert_eq!(actual, Some(8080));
+        }
+
+        #[test]
+        fn test_returns_none_for_unset_value() {
+            env::remove_var("UNSET_KEY"); // Ensure the variable is not set
+            let handler = EnvHandler::new();
+            let actual = handler.get::<u16>("UNSET_KEY").unwrap();
+            assert_eq!(actual, None);
+        }
+
+        #[test]
+        fn test_returns_error_for_invalid_value() {
+            let handler = DefaultHandler::new("not_a_number");
+            let actual = handler.get::<u16>("port");
+            match actual {
+                Err(Error::InvalidValue {
+                    key,
+                    value,
+                    expected,
+                    ..
+                }) => {
+                    assert_eq!(key, "port");
+                    assert_eq!(value, "not_a_number");
+                    assert_eq!(expected, "u16");
+                }
+                other => panic!("unexpected result: {other:?}"),
+            }
+        }
+
+        #[test]
+        fn test_parses_value_through_boxed_handler() {
+            let handler: Box<dyn Handler> = Box::new(DefaultHandler::new("true"));
+            let actual = handler.get::<bool>("flag").unwrap();
+            assert_eq!(actual, Some(true));
+        }
+
+        #[test]
+        fn test_deserializes_json_array() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, r#"{{"ports": [80, 443]}}"#).unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path().to_str().unwrap());
+            let actual = handler.get_deserialized::<Vec<u16>>("ports").unwrap();
+            assert_eq!(actual, Some(vec![80, 443]));
+        }
+
+        #[test]
+        fn test_returns_error_for_failed_source() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, "not json").unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path());
+            let actual = handler.get::<u16>("port");
+            assert!(matches!(actual, Err(Error::Parse { .. })));
+        }
+
+        #[test]
+        fn test_deserializes_plain_string() {
+            let handler = DefaultHandler::new("debug");
+            let actual = handler.get_deserialized::<String>("verbosity").unwrap();
+            assert_eq!(actual, Some("debug".to_string()));
+        }
+
+        #[test]
+        fn test_deserializes_json_scalar_as_string() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(
+                temp_file,
+                r#"{{"port": 8080, "tls": true, "version": "1.0"}}"#
+            )
+            .unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path());
+            let actual = handler.get_deserialized::<String>("port").unwrap();
+            assert_eq!(actual, Some("8080".to_string()));
+            let actual = handler.get_deserialized::<String>("tls").unwrap();
+            assert_eq!(actual, Some("true".to_string()));
+            let actual = handler.get_deserialized::<String>("version").unwrap();
+            assert_eq!(actual, Some("1.0".to_string()));
+            let actual = handler.get_deserialized::<u16>("port").unwrap();
+            assert_eq!(actual, Some(8080));
+        }
+    }
+
+    mod chain {
+        use super::*;
+
+        #[test]
+        fn test_returns_first_value() {
+            let handler = Chain::new()
+                .with(DefaultHandler::new("FIRST"))
+                .with(DefaultHandler::new("SECOND"));
+            let actual = handler.handle_request("");
+            assert_eq!(actual, Some("FIRST".to_string()));
+        }
+
+        #[test]
+        fn test_returns_none_when_empty() {
+            let handler = Chain::new();
+            let actual = handler.handle_request("");
+            assert_eq!(actual, None);
+        }
+
+        #[test]
+        fn test_insert_before_changes_order() {
+            let mut handler = Chain::new().with(DefaultHandler::new("SECOND"));
+            handler.insert_before(0, DefaultHandler::new("FIRST"));
+            let actual = handler.handle_request("");
+            assert_eq!(actual, Some("FIRST".to_string()));
+        }
+
+        #[test]
+        fn test_remove_changes_order() {
+            let mut handler = Chain::new()
+                .with(DefaultHandler::new("FIRST"))
+                .with(DefaultHandler::new("SECOND"));
+            let removed = handler.remove(0).unwrap();
+            assert_eq!(removed.handle_request(""), Some("FIRST".to_string()));
+            assert_eq!(handler.handle_request(""), Some("SECOND".to_string()));
+            assert!(handler.remove(1).is_none());
+        }
+
+        #[test]
+        fn test_iterates_in_order() {
+            let mut handler = Chain::new();
+            handler.push(DefaultHandler::new("FIRST"));
+            handler.push(DefaultHandler::new("SECOND"));
+            let actual: Vec<_> = handler.iter().map(|h| h.handle_request("")).collect();
+            let expected = vec![Some("FIRST".to_string()), Some("SECOND".to_string())];
+            assert_eq!(actual, expected);
+        }
+
+        #[test]
+        fn test_nested_chain() {
+            let handler = Chain::new()
+                .with(Chain::new().with(FileHandler::new("")))
+                .with(DefaultHandler::new("DEFAULT_VALUE"));
+            let actual = handler.handle_request("");
+            assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
+        }
+
+        #[test]
+        fn test_try_stops_at_error() {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let handler = Chain::new()
+                .with(FileHandler::new(temp_dir.path()))
+                .with(DefaultHandler::new("DEFAULT_VALUE"));
+            let actual = handler.try_handle_request("");
+            assert!(matches!(actual, Err(Error::Io { .. })));
+        }
+
+        #[test]
+        fn test_try_with_source_and_all_stop_at_error() {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let handler = Chain::new()
+                .with(FileHandler::new(temp_dir.path()))
+                .with(DefaultHandler::new("DEFAULT_VALUE"));
+            let actual = handler.try_handle_request_with_source("");
+            assert!(matches!(actual, Err(Error::Io { .. })));
+            let actual = handler.try_handle_request_all("");
+            assert!(matches!(actual, Err(Error::Io { .. })));
+        }
+
+        #[test]
+        fn test_queries_each_handler_once() {
+            /// A handler counting how often it is queried.
+            struct CountingHandler(std::sync::atomic::AtomicUsize);
+
+            impl Handler for CountingHandler {
+                fn handle_request(&self, _key: &str) -> Option<String> {
+                    self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    Some("COUNTED".to_string())
+                }
+            }
+
+            let counter = CountingHandler(Default::default());
+            let handler = Chain::new().with(&counter);
+            handler.handle_request_with_source("");
+            handler.handle_request_all("");
+            assert_eq!(counter.0.load(std::sync::atomic::Ordering::SeqCst), 2);
+        }
+    }
+
+    mod error_policy {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        use super::*;
+
+        fn malformed_json() -> NamedTempFile {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, r#"{{"test_key": }}"#).unwrap();
+            temp_file
+        }
+
+        fn chain<H: Handler + 'static>(handler: H) -> Chain<'static> {
+            Chain::new()
+                .with(handler)
+                .with(DefaultHandler::new("DEFAULT_VALUE"))
+        }
+
+        #[test]
+        fn test_defaults_to_fail() {
+            assert_eq!(ErrorPolicy::default(), ErrorPolicy::Fail);
+            assert_eq!(FileHandler::new("").error_policy(), ErrorPolicy::Fail);
+            assert_eq!(JSONFileHandler::new("").error_policy(), ErrorPolicy::Fail);
+            assert_eq!(EnvHandler::new().error_policy(), ErrorPolicy::Fail);
+        }
+
+        #[test]
+        fn test_missing_key_falls_through_for_every_policy() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, r#"{{"test_key": "example"}}"#).unwrap();
+            for policy in [
+                ErrorPolicy::FallThrough,
+                ErrorPolicy::Fail,
+                ErrorPolicy::Warn,
+            ] {
+                let handler = chain(JSONFileHandler::new(temp_file.path()).on_error(policy));
+                let actual = handler.try_handle_request("other_key").unwrap();
+                assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
+            }
+        }
+
+        #[test]
+        fn test_missing_file_falls_through_for_every_policy() {
+            for policy in [
+                ErrorPolicy::FallThrough,
+                ErrorPolicy::Fail,
+                ErrorPolicy::Warn,
+            ] {
+                let handler = chain(FileHandler::new("").on_error(policy));
+                let actual = handler.try_handle_request("test_key").unwrap();
+                assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
+            }
+        }
+
+        #[test]
+        fn test_fail_stops_at_parse_error() {
+            let temp_file = malformed_json();
+            let handler = chain(JSONFileHandler::new(temp_file.path()).on_error(ErrorPolicy::Fail));
+            let actual = handler.try_handle_request("test_key");
+            assert!(matches!(actual, Err(Error::Parse { .. })));
+        }
+
+        #[test]
+        fn test_fail_stops_at_io_error() {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let handler = chain(FileHandler::new(temp_dir.path()).on_error(ErrorPolicy::Fail));
+            let actual = handler.try_handle_request("test_key");
+            assert!(matches!(actual, Err(Error::Io { .. })));
+        }
+
+        #[test]
+        fn test_fall_through_skips_parse_error() {
+            let temp_file = malformed_json();
+            let handler =
+                chain(JSONFileHandler::new(temp_file.path()).on_error(ErrorPolicy::FallThrough));
+            let actual = handler.try_handle_request("test_key").unwrap();
+            assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
+        }
+
+        #[test]
+        fn test_fall_through_skips_io_error() {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let handler =
+                chain(FileHandler::new(temp_dir.path()).on_error(ErrorPolicy::FallThrough));
+            let actual = handler.try_handle_request("test_key").unwrap();
+            assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
+        }
+
+        #[test]
+        fn test_warn_skips_parse_error() {
+            let temp_file = malformed_json();
+            let handler = chain(JSONFileHandler::new(temp_file.path()).on_error(ErrorPolicy::Warn));
+            let actual = handler.try_handle_request("test_key").unwrap();
+            assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
+        }
+
+        #[test]
+        fn test_warn_skips_io_error() {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let handler = chain(FileHandler::new(temp_dir.path()).on_error(ErrorPolicy::Warn));
+            let actual = handler.try_handle_request("test_key").unwrap();
+            assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
+        }
+
+        #[test]
+        fn test_handle_request_skips_error_for_every_policy() {
+            let temp_file = malformed_json();
+            for policy in [
+                ErrorPolicy::FallThrough,
+                ErrorPolicy::Fail,
+                ErrorPolicy::Warn,
+            ] {
+                let handler = chain(JSONFileHandler::new(temp_file.path()).on_error(policy));
+                let expected = Some("DEFAULT_VALUE".to_string());
+                assert_eq!(handler.handle_request("test_key"), expected);
+                let expected = Some(("DEFAULT_VALUE".to_string(), Source::Default));
+                assert_eq!(handler.handle_request_with_source("test_key"), expected);
+                let expected = Some(vec!["DEFAULT_VALUE".to_string()]);
+                assert_eq!(handler.handle_request_all("test_key"), expected);
+            }
+        }
+
+        #[test]
+        fn test_broken_file_ahead_of_default_handler_with_default_policy() {
+            let temp_file = malformed_json();
+            let handler = chain(JSONFileHandler::new(temp_file.path()));
+            assert_eq!(
+                handler.handle_request("test_key"),
+                Some("DEFAULT_VALUE".to_string())
+            );
+            assert!(matches!(
+                handler.try_handle_request("test_key"),
+                Err(Error::Parse { .. })
+            ));
+        }
+
+        #[test]
+        fn test_nested_chain_applies_inner_policy() {
+            let temp_file = malformed_json();
+            let inner = chain(JSONFileHandler::new(temp_file.path()).on_error(ErrorPolicy::Fail));
+            let handler = Chain::new().with(inner);
+            let actual = handler.try_handle_request("test_key");
+            assert!(matches!(actual, Err(Error::Parse { .. })));
+        }
+    }
+
+    mod thread_safety {
+        use super::*;
+
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        #[test]
+        fn test_handlers_are_send_sync() {
+            assert_send_sync::<DefaultHandler>();
+            assert_send_sync::<EnvHandler>();
+            assert_send_sync::<FileHandler>();
+            assert_send_sync::<DirectoryHandler>();
+            assert_send_sync::<JSONFileHandler>();
+            assert_send_sync::<SharedChain>();
+            assert_send_sync::<ConfigFileHandler>();
+            assert_send_sync::<DropInDirHandler>();
+            #[cfg(feature = "clap")]
+            assert_send_sync::<ArgHandler>();
+            #[cfg(feature = "config")]
+            assert_send_sync::<ConfigHandler>();
+        }
+
+        #[test]
+        fn test_chain_accepts_handler_which_is_not_send() {
+            struct RcHandler(std::rc::Rc<String>);
+
+            impl Handler for RcHandler {
+                fn handle_request(&self, _key: &str) -> Option<String> {
+                    Some(self.0.to_string())
+                }
+            }
+
+            let handler = Chain::new().with(RcHandler(std::rc::Rc::new("value".to_string())));
+            assert_eq!(handler.handle_request("key"), Some("value".to_string()));
+        }
+
+        #[test]
+        fn test_shared_chain_queried_from_threads() {
+            let mut json_file = NamedTempFile::new().unwrap();
+            writeln!(json_file, r#"{{"test_key": "JSONFileHandler"}}"#).unwrap();
+
+            let handler = SharedChain::new()
+                .with(JSONFileHandler::new(json_file.path()))
+                .with(DefaultHandler::new("DefaultHandler"));
+            let workers: Vec<_> = (0..4)
+                .map(|_| {
+                    let handler = handler.clone();
+                    std::thread::spawn(move || handler.handle_request("test_key"))
+                })
+                .collect();
+            for worker in workers {
+                let actual = worker.join().unwrap();
+                assert_eq!(actual, Some("JSONFileHandler".to_string()));
+            }
+        }
+    }
+
+    mod default_handler {
+        use super::*;
+
+        #[test]
+        fn test_retrieves_set_value() {
+            let handler = DefaultHandler::new("TEST_VAL");
+            let actual = handler.handle_request("");
+            assert_eq!(actual, Some("TEST_VAL".to_string()));
+        }
+
+        #[test]
+        fn test_cannot_enumerate_keys() {
+            let handler = DefaultHandler::new("TEST_VAL");
+            assert_eq!(handler.keys(), None);
+        }
+
+        #[test]
+        fn test_retrieves_set_value_as_single_element() {
+            let handler = DefaultHandler::new("TEST_VAL");
+            let actual = handler.handle_request_all("");
+            assert_eq!(actual, Some(vec!["TEST_VAL".to_string()]));
+        }
+    }
+
+    #[cfg(feature = "derive")]
+    mod derive {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        use super::*;
 
-    #[cfg(feature = "clap")]
-    #[test]
-    fn test_clap_features_chain_of_responsibility() {
-        env::set_var("TEST_KEY", "EnvHandler");
-        let args = clap::Command::new("test_app")
-            .arg(clap::Arg::new("example").long("example"))
-            .get_matches_from(vec!["test_app", "--example", "ArgHandler"]);
-        let temp_dir = tempfile::tempdir().unwrap();
-        // Don't create the temporary file so the chain keeps going to the end for this test.
-        let raw_file = temp_dir.path().join("should-not-exist.txt");
-        let mut json_file = NamedTempFile::new().unwrap();
-        writeln!(json_file, r#"{{"test_key": "JSONFileHandler"}}"#).unwrap();
+        #[derive(Debug, PartialEq, CorArgs)]
+        struct AppConfig {
+            /// The port to listen on.
+            #[cor(
+                long = "port",
+                short = 'p',
+                env = "DERIVE_TEST_PORT",
+                file_key = "server.port",
+                default = "8080"
+            )]
+            port: u16,
+            #[cor(long, env = "DERIVE_TEST_LOG_LEVEL", file_key = "level")]
+            log_level: String,
+            #[cor(long)]
+            log_file: Option<String>,
+            #[cor(long, short = 'v', default = "false")]
+            verbose: bool,
+        }
 
-        let handler = ArgHandler::new(&args).next(Box::new(
-            EnvHandler::new().next(Box::new(
-                FileHandler::new(raw_file.as_path().to_str().unwrap())
-                    .next(Box::new(JSONFileHandler::new(
-                        json_file.path().to_str().unwrap(),
-                    )))
-                    .next(Box::new(DefaultHandler::new("DefaultHandler"))),
-            )),
-        ));
-        let actual = handler.handle_request("");
-        assert_eq!(actual, Some("DefaultHandler".to_string()));
-    }
+        fn command() -> clap::Command {
+            clap::Command::new("test_app").args(AppConfig::args())
+        }
 
-    #[test]
-    fn test_default_features_chain_of_responsibility() {
-        env::set_var("TEST_KEY", "EnvHandler");
-        let temp_dir = tempfile::tempdir().unwrap();
-        // Don't create the temporary file so the chain keeps going to the end for this test.
-        let raw_file = temp_dir.path().join("should-not-exist.txt");
-        let mut json_file = NamedTempFile::new().unwrap();
-        writeln!(json_file, r#"{{"test_key": "JSONFileHandler"}}"#).unwrap();
+        #[test]
+        fn test_generates_args() {
+            let args = AppConfig::args();
+            let ids: Vec<&str> = args.iter().map(|arg| arg.get_id().as_str()).collect();
+            assert_eq!(ids, vec!["port", "log_level", "log_file", "verbose"]);
+            assert_eq!(args[0].get_long(), Some("port"));
+            assert_eq!(args[0].get_short(), Some('p'));
+            assert_eq!(args[1].get_long(), Some("log-level"));
+            assert_eq!(
+                args[0].get_help().unwrap().to_string(),
+                "The port to listen on."
+            );
+        }
 
-        let handler = EnvHandler::new().next(Box::new(
-            FileHandler::new(raw_file.as_path().to_str().unwrap())
-                .next(Box::new(JSONFileHandler::new(
-                    json_file.path().to_str().unwrap(),
-                )))
-                .next(Box::new(DefaultHandler::new("DefaultHandler"))),
-        ));
-        let actual = handler.handle_request("");
-        assert_eq!(actual, Some("DefaultHandler".to_string()));
+        #[test]
+        fn test_resolves_fields_in_order() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(
+                temp_file,
+                r#"{{"level": "info", "port": 6060, "server": {{"port": 7070}}}}"#
+            )
+            .unwrap();
+            let matches = command().get_matches_from(vec!["test_app", "--log-file", "app.log"]);
+
+            let actual = AppConfig::from_matches(&matches, Some(temp_file.path().into())).unwrap();
+            let expected = AppConfig {
+                port: 7070,
+                log_level: "info".to_string(),
+                log_file: Some("app.log".to_string()),
+                verbose: false,
+            };
+            assert_eq!(actual, expected);
+        }
+
+        #[test]
+        fn test_bool_field_is_flag() {
+            let matches = command().get_matches_from(vec!["test_app", "--log-level", "info", "-v"]);
+
+            let actual = AppConfig::from_matches(&matches, None).unwrap();
+            assert!(actual.verbose);
+        }
+
+        #[test]
+        fn test_bool_field_without_default_is_false() {
+            #[derive(Debug, PartialEq, CorArgs)]
+            struct FlagConfig {
+                #[cor(long)]
+                verbose: bool,
+            }
+
+            let matches = clap::Command::new("test_app")
+                .args(FlagConfig::args())
+                .get_matches_from(vec!["test_app"]);
+            let actual = FlagConfig::from_matches(&matches, None).unwrap();
+            assert_eq!(actual, FlagConfig { verbose: false });
+        }
+
+        #[test]
+        fn test_args_override_env_and_defaults() {
+            #[derive(Debug, PartialEq, CorArgs)]
+            struct EnvConfig {
+                #[cor(long, env = "DERIVE_ENV_TEST_PORT", default = "8080")]
+                port: u16,
+                #[cor(long, env = "DERIVE_ENV_TEST_HOST", default = "localhost")]
+                host: String,
+                #[cor(env = "DERIVE_ENV_TEST_LOG_LEVEL", default = "trace")]
+                log_level: String,
+            }
+
+            env::set_var("DERIVE_ENV_TEST_PORT", "9090");
+            env::set_var("DERIVE_ENV_TEST_HOST", "example.com");
+            let matches = clap::Command::new("test_app")
+                .args(EnvConfig::args())
+                .get_matches_from(vec!["test_app", "--port", "6060"]);
+
+            let actual = EnvConfig::from_matches(&matches, None).unwrap();
+            let expected = EnvConfig {
+                port: 6060,
+                host: "example.com".to_string(),
+                log_level: "trace".to_string(),
+            };
+            assert_eq!(actual, expected);
+        }
+
+        #[test]
+        fn test_reports_missing_required_field() {
+            let handler = DefaultHandler::new("8080");
+            let handler = __private::KeyMapHandler::new(handler).map("port", "port");
+            let actual = AppConfig::from_handler(&handler);
+            assert!(matches!(actual, Err(Error::MissingKey { key }) if key == "log_level"));
+        }
     }
 
-    #[cfg(feature = "config")]
-    #[test]
-    fn test_config_features_chain_of_responsibility() {
-        env::set_var("UNUSED", "EnvHandler");
-        let mut temp_file = tempfile::Builder::new().suffix(".yml").tempfile().unwrap();
-        let expected = r#"
-        ---
-        test_obj:
-            test_key: "test_val"
-        "#;
-        writeln!(temp_file, "{}", unindent::unindent(expected)).unwrap();
+    mod env_handler {
+        use super::*;
 
-        let config = config::Config::builder()
-            .add_source(config::File::new(
-                temp_file.path().to_str().unwrap(),
-                config::FileFormat::Yaml,
-            ))
-            .build()
-            .unwrap();
+        #[test]
+        fn test_retrieves_set_value_without_prefix() {
+            env::set_var("TEST_KEY", "test_value");
+            let handler = EnvHandler::new();
+            let actual = handler.handle_request("TEST_KEY");
+            assert_eq!(actual, Some("test_value".to_string()));
+        }
 
-        let handler = EnvHandler::new().next(Box::new(ConfigHandler::new(Box::new(config))));
-        // let handler = EnvHandler::new().next(Box::<ConfigHandler>::new(config.into()));
-        let actual = handler.handle_request("test_key");
-        assert_eq!(actual, Some("test_val".to_string()));
+        #[test]
+        fn test_retrieves_set_value_with_prefix() {
+            env::set_var("TEST_KEY", "test_value");
+            let handler = EnvHandler::new().prefix("TEST_");
+            let actual = handler.handle_request("KEY");
+            assert_eq!(actual, Some("test_value".to_string()));
+        }
+
+        #[test]
+        fn test_returns_none_for_unset_value() {
+            env::remove_var("UNSET_KEY"); // Ensure the variable is not set
+            let handler = EnvHandler::new();
+            let actual = handler.handle_request("UNSET_KEY");
+            assert_eq!(actual, None);
+        }
+
+        #[test]
+        fn test_next_handler_called() {
+            env::remove_var("UNSET_KEY"); // Ensure the variable is not set
+            let next_handler = DefaultHandler::new("DEFAULT_VALUE");
+            let handler = Chain::new().with(EnvHandler::new()).with(next_handler);
+            let actual = handler.handle_request("UNSET_KEY");
+            assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
+        }
+
+        #[test]
+        fn test_reports_source_with_prefix() {
+            env::set_var("SOURCE_KEY", "test_value");
+            let handler = EnvHandler::new().prefix("SOURCE_");
+            let actual = handler.handle_request_with_source("KEY");
+            let expected = Source::Env {
+                var: "SOURCE_KEY".to_string(),
+            };
+            assert_eq!(actual, Some(("test_value".to_string(), expected)));
+        }
+
+        #[test]
+        fn test_retrieves_all_values_split_on_delimiter() {
+            env::set_var("TEST_LIST", "a:b:c");
+            let handler = EnvHandler::new().delimiter(":");
+            let actual = handler.handle_request_all("TEST_LIST");
+            let expected = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+            assert_eq!(actual, Some(expected));
+        }
+
+        #[test]
+        fn test_retrieves_all_values_without_delimiter() {
+            env::set_var("TEST_UNSPLIT_LIST", "a:b:c");
+            let handler = EnvHandler::new();
+            let actual = handler.handle_request_all("TEST_UNSPLIT_LIST");
+            assert_eq!(actual, Some(vec!["a:b:c".to_string()]));
+        }
+
+        #[test]
+        fn test_enumerates_keys_with_prefix() {
+            env::set_var("ENUM_TEST_KEY", "test_value");
+            let handler = EnvHandler::new().prefix("ENUM_TEST_");
+            let actual = handler.keys();
+            assert_eq!(actual, Some(vec!["KEY".to_string()]));
+        }
+
+        #[test]
+        fn test_reports_source_of_next_handler() {
+            env::remove_var("UNSET_KEY"); // Ensure the variable is not set
+            let next_handler = DefaultHandler::new("DEFAULT_VALUE");
+            let handler = Chain::new().with(EnvHandler::new()).with(next_handler);
+            let actual = handler.handle_request_with_source("UNSET_KEY");
+            assert_eq!(actual, Some(("DEFAULT_VALUE".to_string(), Source::Default)));
+        }
     }
 
-    mod default_handler {
+    #[cfg(feature = "clap")]
+    mod arg_handler {
+        use clap::Arg;
+
         use super::*;
 
         #[test]
         fn test_retrieves_set_value() {
-            let handler = DefaultHandler::new("TEST_VAL");
-            let actual = handler.handle_request("");
-            assert_eq!(actual, Some("TEST_VAL".to_string()));
+            let args = clap::Command::new("test_app")
+                .arg(Arg::new("example").long("example"))
+                .get_matches_from(vec!["test_app", "--example", "test_value"]);
+
+            let handler = ArgHandler::new(&args);
+            let result = handler.handle_request("example");
+            assert_eq!(result, Some("test_value".to_string()));
+        }
+
+        #[test]
+        fn test_returns_none_for_unset_value() {
+            let args = clap::Command::new("test_app")
+                .arg(Arg::new("example").long("example"))
+                .get_matches_from(vec!["test_app"]);
+
+            let handler = ArgHandler::new(&args);
+            let result = handler.handle_request("example");
+            assert_eq!(result, None);
+        }
+
+        #[test]
+        fn test_next_handler_called() {
+            let args = clap::Command::new("test_app")
+                .arg(Arg::new("example").long("example"))
+                .get_matches_from(vec!["test_app"]);
+            let next_handler = DefaultHandler::new("DEFAULT_VALUE");
+            let handler = Chain::new().with(ArgHandler::new(&args)).with(next_handler);
+            let actual = handler.handle_request("example");
+            assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
+        }
+
+        #[test]
+        fn test_enumerates_keys_with_values() {
+            let args = clap::Command::new("test_app")
+                .arg(Arg::new("example").long("example"))
+                .arg(Arg::new("unset").long("unset"))
+                .get_matches_from(vec!["test_app", "--example", "test_value"]);
+
+            let handler = ArgHandler::new(&args);
+            let actual = handler.keys();
+            assert_eq!(actual, Some(vec!["example".to_string()]));
+        }
+
+        #[test]
+        fn test_borrowed_handler_after_other_handlers() {
+            env::remove_var("BORROWED_TEST_example"); // Ensure the variable is not set
+            let args = clap::Command::new("test_app")
+                .arg(Arg::new("example").long("example"))
+                .get_matches_from(vec!["test_app", "--example", "test_value"]);
+
+            let handler = Chain::new()
+                .with(EnvHandler::new().prefix("BORROWED_TEST_"))
+                .with(FileHandler::new(""))
+                .with(ArgHandler::new(&args))
+                .with(DefaultHandler::new("DEFAULT_VALUE"));
+            let actual = handler.handle_request("example");
+            assert_eq!(actual, Some("test_value".to_string()));
+        }
+
+        #[test]
+        fn test_owned_handler_in_shared_chain() {
+            let args = clap::Command::new("test_app")
+                .arg(Arg::new("example").long("example"))
+                .get_matches_from(vec!["test_app", "--example", "test_value"]);
+
+            let handler = SharedChain::new()
+                .with(ArgHandler::owned(args))
+                .with(DefaultHandler::new("DEFAULT_VALUE"));
+            let worker = std::thread::spawn(move || handler.handle_request("example"));
+            assert_eq!(worker.join().unwrap(), Some("test_value".to_string()));
+        }
+
+        #[test]
+        fn test_retrieves_all_values() {
+            let args = clap::Command::new("test_app")
+                .arg(
+                    Arg::new("include")
+                        .long("include")
+                        .action(clap::ArgAction::Append),
+                )
+                .get_matches_from(vec!["test_app", "--include", "a", "--include", "b"]);
+
+            let handler = ArgHandler::new(&args);
+            let actual = handler.handle_request_all("include");
+            assert_eq!(actual, Some(vec!["a".to_string(), "b".to_string()]));
+        }
+
+        #[test]
+        fn test_reports_source() {
+            let args = clap::Command::new("test_app")
+                .arg(Arg::new("example").long("example"))
+                .get_matches_from(vec!["test_app", "--example", "test_value"]);
+
+            let handler = ArgHandler::new(&args);
+            let actual = handler.handle_request_with_source("example");
+            let expected = Source::Arg {
+                id: "example".to_string(),
+            };
+            assert_eq!(actual, Some(("test_value".to_string(), expected)));
         }
     }
 
-    mod env_handler {
+    mod file_handler {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
         use super::*;
 
         #[test]
-        fn test_retrieves_set_value_without_prefix() {
-            env::set_var("TEST_KEY", "test_value");
-            let handler = EnvHandler::new();
-            let actual = handler.handle_request("TEST_KEY");
-            assert_eq!(actual, Some("test_value".to_string()));
+        fn test_retrieves_set_value() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, "test_content").unwrap();
+
+            let handler = FileHandler::new(temp_file.path().to_str().unwrap());
+            let result = handler.handle_request(""); // key is not used in this handler
+            assert_eq!(result, Some("test_content\n".to_string()));
+        }
+
+        #[test]
+        fn test_returns_none_for_nonexistent_file() {
+            let handler = FileHandler::new("");
+            let result = handler.handle_request("example");
+            assert_eq!(result, None);
+        }
+
+        #[test]
+        fn test_next_handler_called() {
+            let next_handler = DefaultHandler::new("DEFAULT_VALUE");
+            let handler = Chain::new().with(FileHandler::new("")).with(next_handler);
+            let actual = handler.handle_request("example");
+            assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
+        }
+
+        #[test]
+        fn test_trims_content() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, "  test_content  ").unwrap();
+
+            let handler = FileHandler::new(temp_file.path()).trim(true);
+            let result = handler.handle_request("");
+            assert_eq!(result, Some("test_content".to_string()));
+        }
+
+        #[test]
+        fn test_retrieves_first_line() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            write!(temp_file, "first line \r\nsecond line\n").unwrap();
+
+            let handler = FileHandler::new(temp_file.path()).first_line(true);
+            assert_eq!(handler.handle_request(""), Some("first line ".to_string()));
+            let handler = FileHandler::new(temp_file.path())
+                .first_line(true)
+                .trim(true);
+            assert_eq!(handler.handle_request(""), Some("first line".to_string()));
+        }
+
+        #[test]
+        fn test_retrieves_first_line_of_empty_file() {
+            let temp_file = NamedTempFile::new().unwrap();
+            let handler = FileHandler::new(temp_file.path()).first_line(true);
+            assert_eq!(handler.handle_request(""), Some("".to_string()));
+        }
+
+        #[test]
+        fn test_reads_file_within_max_size() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            write!(temp_file, "12345").unwrap();
+
+            let handler = FileHandler::new(temp_file.path()).max_size(5);
+            let result = handler.try_handle_request("").unwrap();
+            assert_eq!(result, Some("12345".to_string()));
+        }
+
+        #[test]
+        fn test_try_returns_io_error_for_file_exceeding_max_size() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            write!(temp_file, "123456").unwrap();
+
+            let handler = FileHandler::new(temp_file.path()).max_size(5);
+            match handler.try_handle_request("") {
+                Err(Error::Io { path, source }) => {
+                    assert_eq!(path, temp_file.path());
+                    assert_eq!(source.kind(), io::ErrorKind::InvalidData);
+                }
+                other => panic!("unexpected result: {other:?}"),
+            }
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn test_max_size_stops_reading_endless_file() {
+            let handler = FileHandler::new("/dev/zero").max_size(1024);
+            let result = handler.try_handle_request("");
+            assert!(matches!(result, Err(Error::Io { .. })));
+        }
+
+        #[test]
+        fn test_retrieves_binary_content_as_base64() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            temp_file.write_all(&[0xff, 0x00, 0xfe, b'\n']).unwrap();
+
+            let handler = FileHandler::new(temp_file.path()).base64(true);
+            let result = handler.handle_request("");
+            assert_eq!(result, Some("/wD+Cg==".to_string()));
+        }
+
+        #[test]
+        fn test_try_returns_io_error_for_binary_content() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            temp_file.write_all(&[0xff, 0x00, 0xfe]).unwrap();
+
+            let handler = FileHandler::new(temp_file.path());
+            let result = handler.try_handle_request("");
+            assert!(matches!(result, Err(Error::Io { .. })));
         }
 
         #[test]
-        fn test_retrieves_set_value_with_prefix() {
-            env::set_var("TEST_KEY", "test_value");
-            let handler = EnvHandler::new().prefix("TEST_");
-            let actual = handler.handle_request("KEY");
-            assert_eq!(actual, Some("test_value".to_string()));
+        fn test_reports_source() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, "test_content").unwrap();
+
+            let handler = FileHandler::new(temp_file.path());
+            let actual = handler.handle_request_with_source("");
+            let expected = Source::File {
+                path: temp_file.path().to_path_buf(),
+            };
+            assert_eq!(actual, Some(("test_content\n".to_string(), expected)));
         }
 
         #[test]
-        fn test_returns_none_for_unset_value() {
-            env::remove_var("UNSET_KEY"); // Ensure the variable is not set
-            let handler = EnvHandler::new();
-            let actual = handler.handle_request("UNSET_KEY");
-            assert_eq!(actual, None);
+        fn test_try_returns_none_for_nonexistent_file() {
+            let handler = FileHandler::new("");
+            let result = handler.try_handle_request("example").unwrap();
+            assert_eq!(result, None);
         }
 
         #[test]
-        fn test_next_handler_called() {
-            env::remove_var("UNSET_KEY"); // Ensure the variable is not set
-            let next_handler = Box::new(DefaultHandler::new("DEFAULT_VALUE"));
-            let handler = EnvHandler::new().next(next_handler);
-            let actual = handler.handle_request("UNSET_KEY");
-            assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
+        fn test_try_returns_io_error_for_unreadable_file() {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let next_handler = DefaultHandler::new("DEFAULT_VALUE");
+            let handler = Chain::new()
+                .with(FileHandler::new(temp_dir.path()))
+                .with(next_handler);
+            match handler.try_handle_request("example") {
+                Err(Error::Io { path, .. }) => assert_eq!(path, temp_dir.path()),
+                other => panic!("unexpected result: {other:?}"),
+            }
         }
     }
 
-    #[cfg(feature = "clap")]
-    mod arg_handler {
-        use clap::Arg;
-
+    mod directory_handler {
         use super::*;
 
-        #[test]
-        fn test_retrieves_set_value() {
-            let args = clap::Command::new("test_app")
-                .arg(Arg::new("example").long("example"))
-                .get_matches_from(vec!["test_app", "--example", "test_value"]);
-
-            let handler = ArgHandler::new(&args);
-            let result = handler.handle_request("example");
-            assert_eq!(result, Some("test_value".to_string()));
+        fn secrets_dir() -> tempfile::TempDir {
+            let temp_dir = tempfile::tempdir().unwrap();
+            fs::write(temp_dir.path().join("db_password"), "hunter2\n").unwrap();
+            fs::write(temp_dir.path().join("API_TOKEN"), "token\r\n").unwrap();
+            fs::write(temp_dir.path().join(".hidden"), "hidden").unwrap();
+            fs::create_dir(temp_dir.path().join("..data")).unwrap();
+            temp_dir
         }
 
         #[test]
-        fn test_returns_none_for_unset_value() {
-            let args = clap::Command::new("test_app")
-                .arg(Arg::new("example").long("example"))
-                .get_matches_from(vec!["test_app"]);
+        fn test_retrieves_file_named_after_key() {
+            let temp_dir = secrets_dir();
+            let handler = DirectoryHandler::new(temp_dir.path());
+            let actual = handler.handle_request("db_password");
+            assert_eq!(actual, Some("hunter2\n".to_string()));
+        }
 
-            let handler = ArgHandler::new(&args);
-            let result = handler.handle_request("example");
-            assert_eq!(result, None);
+        #[test]
+        fn test_trims_trailing_newline() {
+            let temp_dir = secrets_dir();
+            let handler = DirectoryHandler::new(temp_dir.path()).trim_newline(true);
+            assert_eq!(
+                handler.handle_request("db_password"),
+                Some("hunter2".to_string())
+            );
+            assert_eq!(
+                handler.handle_request("API_TOKEN"),
+                Some("token".to_string())
+            );
         }
 
         #[test]
-        fn test_next_handler_called() {
-            let args = clap::Command::new("test_app")
-                .arg(Arg::new("example").long("example"))
-                .get_matches_from(vec!["test_app"]);
-            let next_handler = Box::new(DefaultHandler::new("DEFAULT_VALUE"));
-            let handler = ArgHandler::new(&args).next(next_handler);
-            let actual = handler.handle_request("example");
-            assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
+        fn test_maps_key_to_file_name() {
+            let temp_dir = secrets_dir();
+            let handler = DirectoryHandler::new(temp_dir.path())
+                .file_name(|key| key.replace('.', "_").to_uppercase());
+            let actual = handler.handle_request("api.token");
+            assert_eq!(actual, Some("token\r\n".to_string()));
         }
-    }
 
-    mod file_handler {
-        use std::io::Write;
-        use tempfile::NamedTempFile;
+        #[test]
+        fn test_returns_none_for_missing_file() {
+            let temp_dir = secrets_dir();
+            let handler = DirectoryHandler::new(temp_dir.path());
+            let actual = handler.try_handle_request("missing").unwrap();
+            assert_eq!(actual, None);
+        }
 
-        use super::*;
+        #[test]
+        fn test_refuses_keys_outside_directory() {
+            let temp_dir = secrets_dir();
+            let nested = temp_dir.path().join("nested");
+            fs::create_dir(&nested).unwrap();
+            let handler = DirectoryHandler::new(&nested);
+            for key in ["../db_password", "..", "", "/etc/passwd", "a\\b"] {
+                let actual = handler.try_handle_request(key).unwrap();
+                assert_eq!(actual, None, "key {key:?} was not refused");
+            }
+        }
 
         #[test]
-        fn test_retrieves_set_value() {
-            let mut temp_file = NamedTempFile::new().unwrap();
-            writeln!(temp_file, "test_content").unwrap();
+        fn test_refuses_mapped_file_names_outside_directory() {
+            let temp_dir = secrets_dir();
+            let nested = temp_dir.path().join("nested");
+            fs::create_dir(&nested).unwrap();
+            let handler = DirectoryHandler::new(&nested).file_name(|key| format!("../{key}"));
+            let actual = handler.try_handle_request("db_password").unwrap();
+            assert_eq!(actual, None);
+        }
 
-            let handler = FileHandler::new(temp_file.path().to_str().unwrap());
-            let result = handler.handle_request(""); // key is not used in this handler
-            assert_eq!(result, Some("test_content\n".to_string()));
+        #[test]
+        fn test_reports_source_file() {
+            let temp_dir = secrets_dir();
+            let handler = DirectoryHandler::new(temp_dir.path());
+            let (_, source) = handler.handle_request_with_source("db_password").unwrap();
+            let expected = Source::File {
+                path: temp_dir.path().join("db_password"),
+            };
+            assert_eq!(source, expected);
         }
 
         #[test]
-        fn test_returns_none_for_nonexistent_file() {
-            let handler = FileHandler::new("");
-            let result = handler.handle_request("example");
-            assert_eq!(result, None);
+        fn test_enumerates_visible_files() {
+            let temp_dir = secrets_dir();
+            let handler = DirectoryHandler::new(temp_dir.path());
+            let expected = vec!["API_TOKEN".to_string(), "db_password".to_string()];
+            assert_eq!(handler.keys(), Some(expected));
         }
 
         #[test]
         fn test_next_handler_called() {
-            let next_handler = Box::new(DefaultHandler::new("DEFAULT_VALUE"));
-            let handler = FileHandler::new("").next(next_handler);
-            let actual = handler.handle_request("example");
+            let temp_dir = secrets_dir();
+            let handler = Chain::new()
+                .with(DirectoryHandler::new(temp_dir.path()))
+                .with(DefaultHandler::new("DEFAULT_VALUE"));
+            let actual = handler.try_handle_request("missing").unwrap();
             assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
         }
     }
@@ -893,6 +3432,22 @@ mod tests {
             assert_eq!(actual, Some("123".to_string()));
         }
 
+        #[test]
+        fn test_find_key_recursive_agrees_with_handle_request() {
+            let json = r#"{"a": {"port": 1}, "port": 2, "b": {"host": "x"}}"#;
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, "{json}").unwrap();
+            let json_value: Value = serde_json::from_str(json).unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path());
+            for key in ["port", "a.port", "/a/port", "host", "missing"] {
+                let actual = JSONFileHandler::find_key_recursive(&json_value, key);
+                assert_eq!(actual, handler.handle_request(key), "{key}");
+            }
+            let actual = JSONFileHandler::find_key_recursive(&json_value, "port");
+            assert_eq!(actual, Some("2".to_string()));
+        }
+
         #[test]
         fn test_retrieves_set_value_string() {
             let mut temp_file = NamedTempFile::new().unwrap();
@@ -923,6 +3478,38 @@ mod tests {
             assert_eq!(actual, Some("example".to_string()));
         }
 
+        #[test]
+        fn test_retrieves_all_values_in_array() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, r#"{{"test_key": ["a", 1, true]}}"#).unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path());
+            let actual = handler.handle_request_all("test_key");
+            let expected = vec!["a".to_string(), "1".to_string(), "true".to_string()];
+            assert_eq!(actual, Some(expected));
+        }
+
+        #[test]
+        fn test_enumerates_nested_keys() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, r#"{{"test_obj": {{"test_key": "example"}} }}"#).unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path());
+            let actual = handler.keys();
+            let expected = vec!["test_obj".to_string(), "test_key".to_string()];
+            assert_eq!(actual, Some(expected));
+        }
+
+        #[test]
+        fn test_retrieves_all_values_single_value() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, r#"{{"test_key": "example"}}"#).unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path());
+            let actual = handler.handle_request_all("test_key");
+            assert_eq!(actual, Some(vec!["example".to_string()]));
+        }
+
         #[test]
         fn test_returns_none_for_nonexistent_file() {
             let handler = JSONFileHandler::new("");
@@ -932,11 +3519,257 @@ mod tests {
 
         #[test]
         fn test_next_handler_called() {
-            let next_handler = Box::new(DefaultHandler::new("DEFAULT_VALUE"));
-            let handler = JSONFileHandler::new("").next(next_handler);
+            let next_handler = DefaultHandler::new("DEFAULT_VALUE");
+            let handler = Chain::new()
+                .with(JSONFileHandler::new(""))
+                .with(next_handler);
             let actual = handler.handle_request("example");
             assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
         }
+
+        #[test]
+        fn test_try_returns_parse_error_for_malformed_file() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, r#"{{"test_key": }}"#).unwrap();
+
+            let next_handler = DefaultHandler::new("DEFAULT_VALUE");
+            let handler = Chain::new()
+                .with(JSONFileHandler::new(temp_file.path()))
+                .with(next_handler);
+            match handler.try_handle_request("test_key") {
+                Err(Error::Parse { path, .. }) => assert_eq!(path, temp_file.path()),
+                other => panic!("unexpected result: {other:?}"),
+            }
+        }
+
+        #[test]
+        fn test_try_with_source_and_all_return_parse_error_for_malformed_file() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, r#"{{"test_key": }}"#).unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path());
+            let actual = handler.try_handle_request_with_source("test_key");
+            assert!(matches!(actual, Err(Error::Parse { .. })));
+            let actual = handler.try_handle_request_all("test_key");
+            assert!(matches!(actual, Err(Error::Parse { .. })));
+        }
+
+        #[test]
+        fn test_try_next_handler_called_for_missing_key() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, r#"{{"test_key": "example"}}"#).unwrap();
+
+            let next_handler = DefaultHandler::new("DEFAULT_VALUE");
+            let handler = Chain::new()
+                .with(JSONFileHandler::new(temp_file.path()))
+                .with(next_handler);
+            let actual = handler.try_handle_request("other_key").unwrap();
+            assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
+        }
+
+        #[test]
+        fn test_retrieves_dotted_path() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(
+                temp_file,
+                r#"{{"db": {{"host": "a"}}, "cache": {{"host": "b"}}}}"#
+            )
+            .unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path());
+            assert_eq!(handler.handle_request("db.host"), Some("a".to_string()));
+            assert_eq!(handler.handle_request("cache.host"), Some("b".to_string()));
+            assert_eq!(handler.handle_request("db.port"), None);
+        }
+
+        #[test]
+        fn test_retrieves_dotted_path_into_array() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(
+                temp_file,
+                r#"{{"servers": [{{"port": 80}}, {{"port": 81}}]}}"#
+            )
+            .unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path());
+            let actual = handler.handle_request("servers.1.port");
+            assert_eq!(actual, Some("81".to_string()));
+        }
+
+        #[test]
+        fn test_retrieves_literal_dotted_key() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, r#"{{"db.host": "a"}}"#).unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path());
+            let actual = handler.handle_request("db.host");
+            assert_eq!(actual, Some("a".to_string()));
+        }
+
+        #[test]
+        fn test_retrieves_json_pointer() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(
+                temp_file,
+                r#"{{"servers": [{{"port": 80}}, {{"port": 81}}], "a/b": 1}}"#
+            )
+            .unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path());
+            assert_eq!(
+                handler.handle_request("/servers/0/port"),
+                Some("80".to_string())
+            );
+            assert_eq!(handler.handle_request("/a~1b"), Some("1".to_string()));
+            assert_eq!(handler.handle_request("/servers/2/port"), None);
+        }
+
+        #[test]
+        fn test_reports_source_of_json_pointer() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, r#"{{"servers": [{{"port": 80}}]}}"#).unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path());
+            let (_, source) = handler
+                .handle_request_with_source("/servers/0/port")
+                .unwrap();
+            let expected = Source::FileKey {
+                format: "JSON",
+                path: temp_file.path().to_path_buf(),
+                key: "/servers/0/port".to_string(),
+            };
+            assert_eq!(source, expected);
+        }
+
+        #[test]
+        fn test_returns_first_match_for_ambiguous_key() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(
+                temp_file,
+                r#"{{"db": {{"host": "a"}}, "cache": {{"host": "b"}}}}"#
+            )
+            .unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path());
+            let actual = handler.try_handle_request("host").unwrap();
+            assert!(actual.is_some());
+        }
+
+        #[test]
+        fn test_strict_reports_ambiguous_key() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(
+                temp_file,
+                r#"{{"db": {{"host": "a"}}, "cache": {{"host": "b"}}}}"#
+            )
+            .unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path()).strict(true);
+            let actual = handler.try_handle_request("host");
+            match actual {
+                Err(Error::AmbiguousKey {
+                    key,
+                    path,
+                    mut candidates,
+                }) => {
+                    candidates.sort();
+                    assert_eq!(key, "host");
+                    assert_eq!(path, temp_file.path());
+                    assert_eq!(candidates, vec!["cache.host", "db.host"]);
+                }
+                _ => panic!("expected an ambiguous key error, got {actual:?}"),
+            }
+            assert_eq!(handler.handle_request("host"), None);
+        }
+
+        #[test]
+        fn test_strict_retrieves_unique_and_root_keys() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(
+                temp_file,
+                r#"{{"port": 1, "db": {{"port": 2, "user": "admin"}}, "cache": {{"port": 3}}}}"#
+            )
+            .unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path()).strict(true);
+            assert_eq!(
+                handler.try_handle_request("port").unwrap(),
+                Some("1".to_string())
+            );
+            assert_eq!(
+                handler.try_handle_request("user").unwrap(),
+                Some("admin".to_string())
+            );
+        }
+
+        #[test]
+        fn test_returns_match_nearest_to_root_for_ambiguous_key() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(
+                temp_file,
+                r#"{{"app": {{"a": {{"host": "deep"}}, "host": "near"}}, "z": {{"host": "last"}}}}"#
+            )
+            .unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path());
+            let expected = Source::FileKey {
+                format: "JSON",
+                path: temp_file.path().to_path_buf(),
+                key: "app.host".to_string(),
+            };
+            assert_eq!(
+                handler.handle_request_with_source("host"),
+                Some(("near".to_string(), expected))
+            );
+        }
+
+        #[test]
+        fn test_parses_file_once() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, r#"{{"test_key": "first"}}"#).unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path());
+            assert_eq!(
+                handler.handle_request("test_key"),
+                Some("first".to_string())
+            );
+            fs::write(temp_file.path(), r#"{"test_key": "second value"}"#).unwrap();
+            assert_eq!(
+                handler.handle_request("test_key"),
+                Some("first".to_string())
+            );
+        }
+
+        #[test]
+        fn test_revalidate_parses_changed_file() {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            writeln!(temp_file, r#"{{"test_key": "first"}}"#).unwrap();
+
+            let handler = JSONFileHandler::new(temp_file.path()).revalidate(true);
+            assert_eq!(
+                handler.handle_request("test_key"),
+                Some("first".to_string())
+            );
+            fs::write(temp_file.path(), r#"{"test_key": "second value"}"#).unwrap();
+            assert_eq!(
+                handler.handle_request("test_key"),
+                Some("second value".to_string())
+            );
+        }
+
+        #[test]
+        fn test_revalidate_picks_up_created_file() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("config.json");
+
+            let handler = JSONFileHandler::new(&path).revalidate(true);
+            assert_eq!(handler.handle_request("test_key"), None);
+            fs::write(&path, r#"{"test_key": "created"}"#).unwrap();
+            assert_eq!(
+                handler.handle_request("test_key"),
+                Some("created".to_string())
+            );
+        }
     }
 
     #[cfg(feature = "config")]
@@ -948,6 +3781,26 @@ mod tests {
 
         use super::*;
 
+        #[test]
+        fn test_find_key_recursive_agrees_with_handle_request() {
+            let json = r#"{"a": {"port": 1}, "port": 2, "b": {"host": "x"}}"#;
+            let config = Config::builder()
+                .add_source(config::File::from_str(json, config::FileFormat::Json))
+                .build()
+                .unwrap();
+            let table: config::Map<String, config::Value> =
+                config.clone().try_deserialize().unwrap();
+            let config_value = config::Value::new(None, table);
+
+            let handler = ConfigHandler::new(Box::new(config));
+            for key in ["port", "a.port", "/a/port", "host", "missing"] {
+                let actual = ConfigHandler::find_key_recursive(&config_value, key);
+                assert_eq!(actual, handler.handle_request(key), "{key}");
+            }
+            let actual = ConfigHandler::find_key_recursive(&config_value, "port");
+            assert_eq!(actual, Some("2".to_string()));
+        }
+
         #[test]
         fn test_retrieves_set_value_number_as_yaml() {
             let mut temp_file = Builder::new().suffix(".yaml").tempfile().unwrap();
@@ -1012,11 +3865,99 @@ mod tests {
             assert_eq!(actual, Some("test_val".to_string()));
         }
 
+        #[test]
+        fn test_retrieves_all_values_in_array() {
+            let mut temp_file = Builder::new().suffix(".yaml").tempfile().unwrap();
+            let expected = r#"
+            ---
+            test_key:
+                - "a"
+                - "b"
+            "#;
+            writeln!(temp_file, "{}", unindent(expected)).unwrap();
+            let config = config::Config::builder()
+                .add_source(config::File::new(
+                    temp_file.path().to_str().unwrap(),
+                    config::FileFormat::Yaml,
+                ))
+                .build()
+                .unwrap();
+
+            let handler = ConfigHandler::new(Box::new(config));
+            let actual = handler.handle_request_all("test_key");
+            assert_eq!(actual, Some(vec!["a".to_string(), "b".to_string()]));
+        }
+
+        #[test]
+        fn test_enumerates_nested_keys() {
+            let mut temp_file = Builder::new().suffix(".yaml").tempfile().unwrap();
+            let expected = r#"
+            ---
+            test_obj:
+                test_key: "test_val"
+            "#;
+            writeln!(temp_file, "{}", unindent(expected)).unwrap();
+            let config = config::Config::builder()
+                .add_source(config::File::new(
+                    temp_file.path().to_str().unwrap(),
+                    config::FileFormat::Yaml,
+                ))
+                .build()
+                .unwrap();
+
+            let handler = ConfigHandler::new(Box::new(config));
+            let actual = handler.keys();
+            let expected = vec!["test_obj".to_string(), "test_key".to_string()];
+            assert_eq!(actual, Some(expected));
+        }
+
+        #[test]
+        fn test_reports_source_of_nested_object() {
+            let mut temp_file = Builder::new().suffix(".yaml").tempfile().unwrap();
+            let expected = r#"
+            ---
+            test_obj:
+                test_key: "test_val"
+            "#;
+            writeln!(temp_file, "{}", unindent(expected)).unwrap();
+            let config = config::Config::builder()
+                .add_source(config::File::new(
+                    temp_file.path().to_str().unwrap(),
+                    config::FileFormat::Yaml,
+                ))
+                .build()
+                .unwrap();
+
+            let handler = ConfigHandler::new(Box::new(config));
+            let actual = handler.handle_request_with_source("test_key");
+            let expected = Source::Config {
+                key: "test_obj.test_key".to_string(),
+            };
+            assert_eq!(actual, Some(("test_val".to_string(), expected)));
+        }
+
+        #[test]
+        fn test_retrieves_dotted_path() {
+            let config = Config::builder()
+                .set_override("db.host", "db.example.com")
+                .unwrap()
+                .set_override("cache.host", "cache.example.com")
+                .unwrap()
+                .build()
+                .unwrap();
+
+            let handler = ConfigHandler::new(Box::new(config));
+            let actual = handler.handle_request("cache.host");
+            assert_eq!(actual, Some("cache.example.com".to_string()));
+        }
+
         #[test]
         fn test_next_handler_called() {
             let config = Config::default();
-            let next_handler = Box::new(DefaultHandler::new("DEFAULT_VALUE"));
-            let handler = ConfigHandler::new(Box::new(config)).next(next_handler);
+            let next_handler = DefaultHandler::new("DEFAULT_VALUE");
+            let handler = Chain::new()
+                .with(ConfigHandler::new(Box::new(config)))
+                .with(next_handler);
             let actual = handler.handle_request("example");
             assert_eq!(actual, Some("DEFAULT_VALUE".to_string()));
         }
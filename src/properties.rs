@@ -0,0 +1,287 @@
+//! A handler for Java properties files.
+//!
+//! See [`PropertiesFileHandler`] for details.
+
+use serde_json::Map;
+
+use super::*;
+
+/// A handler for retrieving values from a specified `.properties` file.
+///
+/// The file is read in the format of `java.util.Properties`, except that it is decoded as UTF-8,
+/// as is done by most tools reading e.g. `application.properties`:
+///
+/// * Lines whose first non-whitespace character is `#` or `!` are comments.
+/// * A key is separated from its value by `=`, `:` or whitespace, and whitespace surrounding the
+///   separator is ignored.
+/// * A line ending with an odd number of `\` is continued on the next line, without the next
+///   line's leading whitespace.
+/// * Keys and values support the escape sequences `\t`, `\n`, `\r`, `\f` and `\uXXXX`. Any
+///   other escaped character stands for itself, so `\=`, `\:` and `\ ` can be part of a key.
+///
+/// Keys are matched exactly, so the dotted keys used by properties files, such as
+/// `server.port`, are requested as they appear in the file. If a key occurs more than once, the
+/// last value is used.
+///
+/// # Examples
+///
+/// ```
+/// use cor_args::{Handler, PropertiesFileHandler};
+///
+/// // Create a new PropertiesFileHandler specifying a path to a file.
+/// let handler = PropertiesFileHandler::new("application.properties");
+///
+/// // Handle a configuration request matching `server.port` within the file.
+/// let value = handler.handle_request("server.port");
+/// ```
+pub struct PropertiesFileHandler {
+    /// The properties file, together with the cached index of its entries.
+    tree: TreeFile,
+}
+
+impl PropertiesFileHandler {
+    /// Creates a new `PropertiesFileHandler` with the specified file path.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - The path to the properties file from which values are to be retrieved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cor_args::PropertiesFileHandler;
+    ///
+    /// let handler = PropertiesFileHandler::new("application.properties");
+    /// ```
+    #[allow(dead_code)]
+    pub fn new<P>(file_path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        PropertiesFileHandler {
+            tree: TreeFile::new(file_path, "properties"),
+        }
+    }
+
+    /// Parses the content of the file into an object mapping each key to its value.
+    fn parse_content(&self, content: &str) -> Result<Value, String> {
+        let mut entries = Map::new();
+        let mut lines = content.lines().enumerate();
+        while let Some((index, line)) = lines.next() {
+            let line_number = index + 1;
+            let line = line.trim_start();
+            if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+                continue;
+            }
+            let mut logical = line.to_string();
+            while ends_with_continuation(&logical) {
+                logical.pop();
+                match lines.next() {
+                    Some((_, next)) => logical.push_str(next.trim_start()),
+                    None => break,
+                }
+            }
+            let (key, value) = split_entry(&logical);
+            let key = unescape(key).map_err(|e| format!("{e} at line {line_number}"))?;
+            let value = unescape(value).map_err(|e| format!("{e} at line {line_number}"))?;
+            entries.insert(key, Value::String(value));
+        }
+        Ok(Value::Object(entries))
+    }
+}
+
+tree_file_handler!(
+    PropertiesFileHandler,
+    "application.properties",
+    impl TreeFormat
+);
+
+impl TreeFormat for PropertiesFileHandler {
+    /// Resolves a key by its exact name.
+    fn lookup<'v>(
+        &self,
+        _tree: &TreeFile,
+        index: &'v TreeIndex,
+        key: &str,
+    ) -> Result<Option<(&'v Value, String)>, Error> {
+        Ok(index.entry(key))
+    }
+}
+
+/// Returns whether a line ends with an odd number of backslashes.
+fn ends_with_continuation(line: &str) -> bool {
+    line.chars().rev().take_while(|&c| c == '\\').count() % 2 == 1
+}
+
+/// Splits a logical line into its still escaped key and value.
+fn split_entry(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    let mut key_end = line.len();
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '=' | ':' => {
+                key_end = i;
+                break;
+            }
+            c if c.is_whitespace() => {
+                key_end = i;
+                break;
+            }
+            _ => {}
+        }
+    }
+    let rest = line[key_end..].trim_start();
+    let rest = rest.strip_prefix(['=', ':']).map_or(rest, str::trim_start);
+    (&line[..key_end], rest)
+}
+
+/// Replaces the escape sequences within a key or value.
+fn unescape(text: &str) -> Result<String, String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut units = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            flush_utf16(&mut units, &mut unescaped)?;
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                let unit = (hex.len() == 4)
+                    .then(|| u16::from_str_radix(&hex, 16).ok())
+                    .flatten()
+                    .ok_or_else(|| format!("malformed \\uXXXX escape `\\u{hex}`"))?;
+                units.push(unit);
+                continue;
+            }
+            Some(c) => {
+                flush_utf16(&mut units, &mut unescaped)?;
+                unescaped.push(match c {
+                    't' => '\t',
+                    'n' => '\n',
+                    'r' => '\r',
+                    'f' => '\u{c}',
+                    c => c,
+                });
+            }
+            None => flush_utf16(&mut units, &mut unescaped)?,
+        }
+    }
+    flush_utf16(&mut units, &mut unescaped)?;
+    Ok(unescaped)
+}
+
+/// Decodes consecutive `\uXXXX` escapes, which may form surrogate pairs.
+fn flush_utf16(units: &mut Vec<u16>, unescaped: &mut String) -> Result<(), String> {
+    for c in char::decode_utf16(units.drain(..)) {
+        unescaped.push(c.map_err(|e| format!("invalid \\uXXXX escape: {e}"))?);
+    }
+    Ok(())
+}
+
+impl<'a> From<PropertiesFileHandler> for Box<dyn Handler + 'a> {
+    fn from(handler: PropertiesFileHandler) -> Self {
+        Box::new(handler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    fn properties_file(content: &str) -> NamedTempFile {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(temp_file, "{content}").unwrap();
+        temp_file
+    }
+
+    #[test]
+    fn test_retrieves_values_with_each_separator() {
+        let temp_file = properties_file(
+            "# server\n! settings\nserver.port=8080\nserver.host : localhost\nserver.name   example\n",
+        );
+        let handler = PropertiesFileHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("server.port"),
+            Some("8080".to_string())
+        );
+        assert_eq!(
+            handler.handle_request("server.host"),
+            Some("localhost".to_string())
+        );
+        assert_eq!(
+            handler.handle_request("server.name"),
+            Some("example".to_string())
+        );
+        assert_eq!(handler.handle_request("port"), None);
+    }
+
+    #[test]
+    fn test_joins_continuation_lines() {
+        let temp_file =
+            properties_file("fruits = apple, \\\n         banana\npath = C:\\\\\nnext = 1\n");
+        let handler = PropertiesFileHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("fruits"),
+            Some("apple, banana".to_string())
+        );
+        assert_eq!(handler.handle_request("path"), Some("C:\\".to_string()));
+        assert_eq!(handler.handle_request("next"), Some("1".to_string()));
+    }
+
+    #[test]
+    fn test_unescapes_keys_and_values() {
+        let temp_file = properties_file(
+            "key\\ with\\=separators = a\\tb\ngreeting = gr\\u00FC\\u00DFe \\uD83D\\uDE00\n",
+        );
+        let handler = PropertiesFileHandler::new(temp_file.path());
+        assert_eq!(
+            handler.handle_request("key with=separators"),
+            Some("a\tb".to_string())
+        );
+        assert_eq!(
+            handler.handle_request("greeting"),
+            Some("grüße 😀".to_string())
+        );
+    }
+
+    #[test]
+    fn test_retrieves_empty_value() {
+        let temp_file = properties_file("empty\nalso.empty =\n");
+        let handler = PropertiesFileHandler::new(temp_file.path());
+        assert_eq!(handler.handle_request("empty"), Some("".to_string()));
+        assert_eq!(handler.handle_request("also.empty"), Some("".to_string()));
+    }
+
+    #[test]
+    fn test_reports_source() {
+        let temp_file = properties_file("server.port=8080\n");
+        let handler = PropertiesFileHandler::new(temp_file.path());
+        let expected = Source::FileKey {
+            format: "properties",
+            path: temp_file.path().to_path_buf(),
+            key: "server.port".to_string(),
+        };
+        assert_eq!(
+            handler.handle_request_with_source("server.port"),
+            Some(("8080".to_string(), expected))
+        );
+    }
+
+    #[test]
+    fn test_reports_malformed_unicode_escape() {
+        let temp_file = properties_file("a = 1\nb = \\u12G4\n");
+        let handler = PropertiesFileHandler::new(temp_file.path());
+        match handler.try_handle_request("a") {
+            Err(Error::Parse { reason, .. }) => assert!(reason.contains("line 2"), "{reason}"),
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+}
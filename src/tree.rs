@@ -0,0 +1,518 @@
+//! The shared implementation of handlers for tree structured files.
+//!
+//! Each file format is converted into a JSON value once, after which keys are resolved the same
+//! way for every format, as described for [`JSONFileHandler`](super::JSONFileHandler).
+
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+use super::{Error, ErrorPolicy, FileHandler, Source};
+
+/// A tree structured file, together with the cached index of its keys.
+pub(crate) struct TreeFile {
+    /// Underlying file handler used to read content from the specified file.
+    pub(crate) file_handler: FileHandler,
+    /// The name of the file format, as reported by [`Source::FileKey`].
+    format: &'static str,
+    /// Whether bare keys which occur more than once are reported as ambiguous.
+    pub(crate) strict: bool,
+    /// Whether the cached index is checked against the file's modification time on each request.
+    pub(crate) revalidate: bool,
+    /// The index of the file, once it has been parsed.
+    cache: RwLock<Option<Arc<TreeIndex>>>,
+    /// How a chain treats a failure to read or parse the file.
+    pub(crate) error_policy: ErrorPolicy,
+}
+
+impl TreeFile {
+    pub(crate) fn new<P>(file_path: P, format: &'static str) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        TreeFile {
+            file_handler: FileHandler::new(file_path),
+            format,
+            strict: false,
+            revalidate: false,
+            cache: RwLock::new(None),
+            error_policy: ErrorPolicy::default(),
+        }
+    }
+
+    /// Returns the path of the file.
+    pub(crate) fn file_path(&self) -> &Path {
+        &self.file_handler.file_path
+    }
+
+    /// Returns the index of the file, reading it and converting it with `parse` if necessary.
+    ///
+    /// Failures are not cached, so a file that could not be read or parsed is tried again on the
+    /// next request.
+    ///
+    /// # Returns
+    ///
+    /// The index, which is empty if the file does not exist, an [`Error::Io`] if the file could
+    /// not be read, or an [`Error::Parse`] if `parse` fails.
+    pub(crate) fn index<F>(&self, parse: F) -> Result<Arc<TreeIndex>, Error>
+    where
+        F: FnOnce(&str) -> Result<Value, String>,
+    {
+        let stamp = if self.revalidate {
+            file_stamp(self.file_path())
+        } else {
+            None
+        };
+        if let Some(cached) = self.cache.read().unwrap().as_ref() {
+            if !self.revalidate || cached.stamp == stamp {
+                return Ok(Arc::clone(cached));
+            }
+        }
+        let json = match self.file_handler.read()? {
+            Some(file_data) => {
+                Some(parse(&file_data).map_err(|e| Error::parse(self.file_path(), e))?)
+            }
+            None => None,
+        };
+        let index = Arc::new(TreeIndex::new(json, stamp));
+        *self.cache.write().unwrap() = Some(Arc::clone(&index));
+        Ok(index)
+    }
+
+    /// Resolves a key within the parsed structure as described for
+    /// [`JSONFileHandler`](super::JSONFileHandler).
+    ///
+    /// # Returns
+    ///
+    /// If found, returns the value associated with the key together with the path at which it
+    /// was found, or an [`Error::AmbiguousKey`] if the key is ambiguous in strict mode.
+    pub(crate) fn find_value<'v>(
+        &self,
+        index: &'v TreeIndex,
+        key: &str,
+    ) -> Result<Option<(&'v Value, String)>, Error> {
+        if !self.strict || key.starts_with('/') {
+            return Ok(index.find_value(key));
+        }
+        let Some(json_value) = index.json.as_ref() else {
+            return Ok(None);
+        };
+        if key.contains('.') {
+            if let Some(value) = find_value_by_path(json_value, key) {
+                return Ok(Some((value, key.to_string())));
+            }
+        }
+        let Some(locations) = index.locations.get(key) else {
+            return Ok(None);
+        };
+        if let Some(value) = json_value.as_object().and_then(|map| map.get(key)) {
+            return Ok(Some((value, key.to_string())));
+        }
+        match locations.as_slice() {
+            [(pointer, path)] => Ok(json_value
+                .pointer(pointer)
+                .map(|value| (value, path.clone()))),
+            _ => Err(Error::AmbiguousKey {
+                key: key.to_string(),
+                path: self.file_path().to_path_buf(),
+                candidates: locations.iter().map(|(_, path)| path.clone()).collect(),
+            }),
+        }
+    }
+
+    pub(crate) fn try_handle_request<T, F>(
+        &self,
+        format: &T,
+        key: &str,
+        parse: F,
+    ) -> Result<Option<String>, Error>
+    where
+        T: TreeFormat + ?Sized,
+        F: FnOnce(&str) -> Result<Value, String>,
+    {
+        let index = self.index(parse)?;
+        Ok(format
+            .lookup(self, &index, key)?
+            .map(|(value, _)| value_to_string(value)))
+    }
+
+    pub(crate) fn try_handle_request_with_source<T, F>(
+        &self,
+        format: &T,
+        key: &str,
+        parse: F,
+    ) -> Result<Option<(String, Source)>, Error>
+    where
+        T: TreeFormat + ?Sized,
+        F: FnOnce(&str) -> Result<Value, String>,
+    {
+        let index = self.index(parse)?;
+        let Some((value, path)) = format.lookup(self, &index, key)? else {
+            return Ok(None);
+        };
+        let source = Source::FileKey {
+            format: self.format,
+            path: self.file_path().to_path_buf(),
+            key: path,
+        };
+        Ok(Some((value_to_string(value), source)))
+    }
+
+    pub(crate) fn try_handle_request_all<T, F>(
+        &self,
+        format: &T,
+        key: &str,
+        parse: F,
+    ) -> Result<Option<Vec<String>>, Error>
+    where
+        T: TreeFormat + ?Sized,
+        F: FnOnce(&str) -> Result<Value, String>,
+    {
+        let index = self.index(parse)?;
+        Ok(format
+            .lookup(self, &index, key)?
+            .map(|(value, _)| match value {
+                Value::Array(arr) => arr.iter().map(value_to_string).collect(),
+                _ => vec![value_to_string(value)],
+            }))
+    }
+
+    pub(crate) fn keys<T, F>(&self, format: &T, parse: F) -> Option<Vec<String>>
+    where
+        T: TreeFormat + ?Sized,
+        F: FnOnce(&str) -> Result<Value, String>,
+    {
+        let keys = self
+            .index(parse)
+            .map(|index| format.key_names(&index))
+            .unwrap_or_default();
+        Some(keys)
+    }
+}
+
+/// How keys are resolved within the index of a file.
+///
+/// The defaults resolve keys as described for [`JSONFileHandler`](super::JSONFileHandler).
+/// Formats whose keys are laid out differently, e.g. INI sections, override them.
+pub(crate) trait TreeFormat {
+    /// Resolves a key within the index of `tree`.
+    ///
+    /// # Returns
+    ///
+    /// If found, returns the value associated with the key together with the key to report in
+    /// [`Source::FileKey`].
+    fn lookup<'v>(
+        &self,
+        tree: &TreeFile,
+        index: &'v TreeIndex,
+        key: &str,
+    ) -> Result<Option<(&'v Value, String)>, Error> {
+        tree.find_value(index, key)
+    }
+
+    /// Returns the names of all keys which can be requested.
+    fn key_names(&self, index: &TreeIndex) -> Vec<String> {
+        index.names().to_vec()
+    }
+}
+
+/// A parsed file together with the locations of all of its keys.
+pub(crate) struct TreeIndex {
+    /// The modification time and size of the file when it was parsed, if they were checked.
+    stamp: Option<(SystemTime, u64)>,
+    /// The root of the structure, or `None` if the file does not exist.
+    json: Option<Value>,
+    /// The JSON Pointer and dotted path of every location of each key name, in document order.
+    locations: HashMap<String, Vec<(String, String)>>,
+    /// The names of all keys in document order, without duplicates.
+    names: Vec<String>,
+}
+
+impl TreeIndex {
+    pub(crate) fn new(json: Option<Value>, stamp: Option<(SystemTime, u64)>) -> Self {
+        let mut index = TreeIndex {
+            stamp,
+            json: None,
+            locations: HashMap::new(),
+            names: Vec::new(),
+        };
+        if let Some(json) = json.as_ref() {
+            index.add_recursive(json, "", "");
+        }
+        let mut seen = HashSet::new();
+        index.names.retain(|name| seen.insert(name.clone()));
+        index.json = json;
+        index
+    }
+
+    /// Returns the root of the structure, or `None` if the file does not exist.
+    pub(crate) fn json(&self) -> Option<&Value> {
+        self.json.as_ref()
+    }
+
+    /// Returns the names of all keys in document order, without duplicates.
+    pub(crate) fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Returns the entry at the root of the structure named exactly `key`, without resolving
+    /// paths or nested keys.
+    pub(crate) fn entry(&self, key: &str) -> Option<(&Value, String)> {
+        let value = self.json.as_ref()?.get(key)?;
+        Some((value, key.to_string()))
+    }
+
+    /// Resolves a key as described for [`JSONFileHandler`](super::JSONFileHandler), returning
+    /// the first match for a bare key which occurs more than once.
+    ///
+    /// # Returns
+    ///
+    /// If found, returns the value associated with the key together with the path at which it
+    /// was found.
+    pub(crate) fn find_value(&self, key: &str) -> Option<(&Value, String)> {
+        let json_value = self.json.as_ref()?;
+        if key.starts_with('/') {
+            return json_value
+                .pointer(key)
+                .map(|value| (value, key.to_string()));
+        }
+        if key.contains('.') {
+            if let Some(value) = find_value_by_path(json_value, key) {
+                return Some((value, key.to_string()));
+            }
+        }
+        let (pointer, path) = self.locations.get(key)?.first()?;
+        json_value
+            .pointer(pointer)
+            .map(|value| (value, path.clone()))
+    }
+
+    /// Recursively records the location of every key below `json_value`.
+    ///
+    /// The keys of an object are recorded before the keys nested below them, so the first
+    /// location of a key is the one nearest to the root along the first branch containing it.
+    fn add_recursive(&mut self, json_value: &Value, pointer: &str, path: &str) {
+        let join = |name: &str| {
+            let pointer = format!("{pointer}/{}", name.replace('~', "~0").replace('/', "~1"));
+            let path = if path.is_empty() {
+                name.to_string()
+            } else {
+                format!("{path}.{name}")
+            };
+            (pointer, path)
+        };
+        match json_value {
+            Value::Object(map) => {
+                for name in map.keys() {
+                    self.locations
+                        .entry(name.clone())
+                        .or_default()
+                        .push(join(name));
+                }
+                for (name, value) in map.iter() {
+                    self.names.push(name.clone());
+                    let (pointer, path) = join(name);
+                    self.add_recursive(value, &pointer, &path);
+                }
+            }
+            Value::Array(arr) => {
+                for (index, value) in arr.iter().enumerate() {
+                    let (pointer, path) = join(&index.to_string());
+                    self.add_recursive(value, &pointer, &path);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Follows a dotted path, e.g. `"servers.0.port"`, from the root of the structure.
+fn find_value_by_path<'v>(json_value: &'v Value, key: &str) -> Option<&'v Value> {
+    key.split('.')
+        .try_fold(json_value, |value, segment| match value {
+            Value::Object(map) => map.get(segment),
+            Value::Array(arr) => segment.parse::<usize>().ok().and_then(|i| arr.get(i)),
+            _ => None,
+        })
+}
+
+/// Converts a value into the `String` returned by a handler.
+///
+/// Strings are returned without quotes, all other values as their JSON representation.
+pub(crate) fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.as_str().to_string(),
+        _ => value.to_string(),
+    }
+}
+
+/// Returns the modification time and size of a file, or `None` if they cannot be determined.
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Implements the builder methods shared by all file handlers built on [`TreeFile`] and the
+/// [`Handler`] trait for `$name`.
+///
+/// `$name` must have a field `tree: TreeFile` and a method
+/// `fn parse_content(&self, content: &str) -> Result<Value, String>` converting the content of
+/// the file into a JSON value.
+///
+/// Keys are resolved as described for [`JSONFileHandler`](super::JSONFileHandler), and the
+/// `strict` builder is added, unless the invocation ends with `impl TreeFormat`, in which case
+/// `$name` must implement [`TreeFormat`] itself.
+///
+/// [`Handler`]: super::Handler
+macro_rules! tree_file_handler {
+    ($name:ident, $example_path:literal) => {
+        tree_file_handler!($name, $example_path, impl TreeFormat);
+
+        impl TreeFormat for $name {}
+
+        impl $name {
+            /// Sets whether bare keys which occur more than once are reported as ambiguous.
+            ///
+            /// In strict mode, [`Handler::try_handle_request`] returns an
+            /// [`Error::AmbiguousKey`] for a bare key which is not at the root and is found at
+            /// more than one location, instead of returning the first match. The other lookup
+            /// methods treat an ambiguous key as missing.
+            ///
+            /// # Arguments
+            ///
+            /// * `strict` - `true` to report ambiguous keys, `false` to return the first match.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            #[doc = concat!("use cor_args::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let handler = ", stringify!($name), "::new(\"", $example_path, "\").strict(true);")]
+            /// ```
+            #[allow(dead_code)]
+            pub fn strict(mut self, strict: bool) -> Self {
+                self.tree.strict = strict;
+                self
+            }
+        }
+    };
+    ($name:ident, $example_path:literal, impl TreeFormat) => {
+        impl $name {
+            /// Sets how a chain treats a failure to read or parse the file.
+            ///
+            /// # Arguments
+            ///
+            /// * `error_policy` - The policy applied to read and parse errors. Defaults to
+            ///   [`ErrorPolicy::Fail`].
+            ///
+            /// # Examples
+            ///
+            /// ```
+            #[doc = concat!("use cor_args::{ErrorPolicy, ", stringify!($name), "};")]
+            ///
+            #[doc = concat!("let handler = ", stringify!($name), "::new(\"", $example_path, "\").on_error(ErrorPolicy::Warn);")]
+            /// ```
+            #[allow(dead_code)]
+            pub fn on_error(mut self, error_policy: ErrorPolicy) -> Self {
+                self.tree.error_policy = error_policy;
+                self
+            }
+
+            /// Sets whether the file is parsed again when it changes.
+            ///
+            /// By default the file is parsed only once. With revalidation enabled, the
+            /// modification time and size of the file are compared with those of the parsed file
+            /// on every request, and the file is parsed again if either differs.
+            ///
+            /// # Arguments
+            ///
+            /// * `revalidate` - `true` to pick up changes to the file, `false` to parse it only
+            ///   once.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            #[doc = concat!("use cor_args::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let handler = ", stringify!($name), "::new(\"", $example_path, "\").revalidate(true);")]
+            /// ```
+            #[allow(dead_code)]
+            pub fn revalidate(mut self, revalidate: bool) -> Self {
+                self.tree.revalidate = revalidate;
+                self
+            }
+        }
+
+        impl Handler for $name {
+            /// Retrieves a value for the specified key from the file.
+            ///
+            /// # Returns
+            ///
+            /// An `Option` containing the value associated with the key, or `None` if the key is
+            /// not found or the file could not be read or parsed.
+            fn handle_request(&self, key: &str) -> Option<String> {
+                self.try_handle_request(key).ok().flatten()
+            }
+
+            /// Retrieves a value for the specified key from the file, reporting read and parse
+            /// failures.
+            ///
+            /// A file that does not exist, or that does not contain the key, is treated as a
+            /// missing value. A file that cannot be read is returned as an [`Error::Io`] and a
+            /// file that cannot be parsed is returned as an [`Error::Parse`]. In strict mode, an
+            /// ambiguous key is returned as an [`Error::AmbiguousKey`].
+            fn try_handle_request(&self, key: &str) -> Result<Option<String>, Error> {
+                self.tree
+                    .try_handle_request(self, key, |content| self.parse_content(content))
+            }
+
+            /// Retrieves a value for the specified key, reporting the [`Source::FileKey`] it came
+            /// from.
+            ///
+            /// The reported key is the full path at which the key was found within the file, or
+            /// the JSON Pointer if the key is one.
+            fn handle_request_with_source(&self, key: &str) -> Option<(String, Source)> {
+                self.try_handle_request_with_source(key).ok().flatten()
+            }
+
+            /// Retrieves a value for the specified key, reporting the [`Source::FileKey`] it came
+            /// from and read and parse failures.
+            fn try_handle_request_with_source(
+                &self,
+                key: &str,
+            ) -> Result<Option<(String, Source)>, Error> {
+                self.tree
+                    .try_handle_request_with_source(self, key, |content| self.parse_content(content))
+            }
+
+            /// Retrieves all values for the specified key from the file.
+            ///
+            /// If the key is associated with an array, each element is returned as a separate
+            /// value. Otherwise the value is returned as the only element.
+            fn handle_request_all(&self, key: &str) -> Option<Vec<String>> {
+                self.try_handle_request_all(key).ok().flatten()
+            }
+
+            /// Retrieves all values for the specified key from the file, reporting read and parse
+            /// failures.
+            fn try_handle_request_all(&self, key: &str) -> Result<Option<Vec<String>>, Error> {
+                self.tree
+                    .try_handle_request_all(self, key, |content| self.parse_content(content))
+            }
+
+            /// Enumerates the names of all keys within the file.
+            fn keys(&self) -> Option<Vec<String>> {
+                self.tree.keys(self, |content| self.parse_content(content))
+            }
+
+            fn error_policy(&self) -> ErrorPolicy {
+                self.tree.error_policy
+            }
+        }
+    };
+}
+
+pub(crate) use tree_file_handler;